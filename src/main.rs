@@ -1,18 +1,318 @@
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect, Select};
+use futures::future::join_all;
+use notify::Watcher;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::{IsTerminal, Write};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, warn};
+use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
-#[command(name = "service-manager")]
+#[command(name = "service-manager", version)]
 #[command(about = "macOS Service Manager - Manage system services")]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Print the commands that would be executed without running them"
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "dry_run",
+        help = "Describe in plain words what an action would do, then stop without running it"
+    )]
+    explain: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Target the gui/<UID> domain of another user (requires root)"
+    )]
+    uid: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "Log level for diagnostics on stderr (overrides RUST_LOG)"
+    )]
+    log_level: Option<LogLevel>,
+    #[arg(
+        long,
+        global = true,
+        help = "Print machine-readable JSON instead of text"
+    )]
+    json: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECS",
+        help = "Reuse the parsed service list for read-only commands if it is younger than SECS"
+    )]
+    cache_ttl: Option<u64>,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = DEFAULT_CONCURRENCY,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Maximum number of services a batch, group or apply run acts on at once"
+    )]
+    concurrency: u16,
+    #[arg(
+        long,
+        global = true,
+        help = "Print how long each launchctl/brew call and the whole command took to stderr"
+    )]
+    timings: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Read aliases and notes from PATH instead of ~/.config/service-manager/config.toml"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Show configured aliases instead of the raw labels"
+    )]
+    alias: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Run only the privileged launchctl/brew call through sudo when the service needs root"
+    )]
+    sudo: bool,
+    #[arg(long, global = true, help = "Disable colored output")]
+    no_color: bool,
+    #[arg(long, global = true, help = "Replace emoji with plain text tags")]
+    no_emoji: bool,
     #[command(subcommand)]
     command: Commands,
 }
 
+const DEFAULT_CONCURRENCY: u16 = 4;
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+const UNLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MIN_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_WATCH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+static NO_EMOJI: AtomicBool = AtomicBool::new(false);
+
+// `None` until --timings turns collection on.
+static TIMINGS: std::sync::Mutex<Option<Vec<(String, Duration)>>> = std::sync::Mutex::new(None);
+static STARTED: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Icon {
+    Ok,
+    Error,
+    Warning,
+    Stop,
+    Start,
+    Reload,
+    Disable,
+    Summary,
+    Info,
+    Note,
+    Empty,
+    Match,
+    DryRun,
+    Duplicate,
+    Top,
+    Services,
+    Watch,
+    Env,
+    Orphan,
+    Removed,
+    Skipped,
+    Hidden,
+    Doctor,
+    Capabilities,
+    Snapshot,
+    Time,
+    Hint,
+    Notice,
+    Running,
+    Stopped,
+    Other,
+    Disabled,
+    Available,
+}
+
+impl Icon {
+    #[cfg(test)]
+    const ALL: [Icon; 33] = [
+        Icon::Ok,
+        Icon::Error,
+        Icon::Warning,
+        Icon::Stop,
+        Icon::Start,
+        Icon::Reload,
+        Icon::Disable,
+        Icon::Summary,
+        Icon::Info,
+        Icon::Note,
+        Icon::Empty,
+        Icon::Match,
+        Icon::DryRun,
+        Icon::Duplicate,
+        Icon::Top,
+        Icon::Services,
+        Icon::Watch,
+        Icon::Env,
+        Icon::Orphan,
+        Icon::Removed,
+        Icon::Skipped,
+        Icon::Hidden,
+        Icon::Doctor,
+        Icon::Capabilities,
+        Icon::Snapshot,
+        Icon::Time,
+        Icon::Hint,
+        Icon::Notice,
+        Icon::Running,
+        Icon::Stopped,
+        Icon::Other,
+        Icon::Disabled,
+        Icon::Available,
+    ];
+
+    fn glyph(self) -> &'static str {
+        match self {
+            Icon::Ok => "✅",
+            Icon::Error => "❌",
+            Icon::Warning => "⚠️ ",
+            Icon::Stop => "🛑",
+            Icon::Start => "🚀",
+            Icon::Reload => "🔄",
+            Icon::Disable => "🚫",
+            Icon::Summary => "📊",
+            Icon::Info => "📋",
+            Icon::Note => "📝",
+            Icon::Empty => "📭",
+            Icon::Match => "🔍",
+            Icon::DryRun => "🔎",
+            Icon::Duplicate => "🔁",
+            Icon::Top => "🔥",
+            Icon::Services => "🔧",
+            Icon::Watch => "👀",
+            Icon::Env => "🌱",
+            Icon::Orphan => "🧹",
+            Icon::Removed => "🗑️ ",
+            Icon::Skipped => "⏭️ ",
+            Icon::Hidden => "🙈",
+            Icon::Doctor => "🩺",
+            Icon::Capabilities => "🧭",
+            Icon::Snapshot => "📦",
+            Icon::Time => "⏱️",
+            Icon::Hint => "💡",
+            Icon::Notice => "ℹ️",
+            Icon::Running => "🟢",
+            Icon::Stopped => "🔴",
+            Icon::Other => "🟡",
+            Icon::Disabled => "⚫",
+            Icon::Available => "⚪",
+        }
+    }
+
+    // --no-emoji swaps each glyph for a plain tag so logs and screen readers stay legible.
+    fn tag(self) -> &'static str {
+        match self {
+            Icon::Ok => "[ok]",
+            Icon::Error => "[error]",
+            Icon::Warning => "[warn]",
+            Icon::Stop => "[stop]",
+            Icon::Start => "[start]",
+            Icon::Reload => "[reload]",
+            Icon::Disable => "[disable]",
+            Icon::Summary => "[summary]",
+            Icon::Info => "[info]",
+            Icon::Note => "[note]",
+            Icon::Empty => "[empty]",
+            Icon::Match => "[match]",
+            Icon::DryRun => "[dry-run]",
+            Icon::Duplicate => "[duplicate]",
+            Icon::Top => "[top]",
+            Icon::Services => "[services]",
+            Icon::Watch => "[watch]",
+            Icon::Env => "[env]",
+            Icon::Orphan => "[orphan]",
+            Icon::Removed => "[removed]",
+            Icon::Skipped => "[skipped]",
+            Icon::Hidden => "[hidden]",
+            Icon::Doctor => "[doctor]",
+            Icon::Capabilities => "[capabilities]",
+            Icon::Snapshot => "[snapshot]",
+            Icon::Time => "[time]",
+            Icon::Hint => "[hint]",
+            Icon::Notice => "[notice]",
+            Icon::Running => "[running]",
+            Icon::Stopped => "[stopped]",
+            Icon::Other => "[other]",
+            Icon::Disabled => "[disabled]",
+            Icon::Available => "[available]",
+        }
+    }
+}
+
+fn icon(icon: Icon) -> &'static str {
+    if NO_EMOJI.load(Ordering::Relaxed) {
+        icon.tag()
+    } else {
+        icon.glyph()
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn as_filter(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+fn init_logging(level: Option<LogLevel>) {
+    let filter = match level {
+        Some(level) => EnvFilter::new(level.as_filter()),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 #[derive(Subcommand)]
 enum Commands {
     List {
@@ -20,434 +320,7533 @@ enum Commands {
         running: bool,
         #[arg(short, long, help = "Include brew services")]
         brew: bool,
+        #[arg(long, help = "Page the output through $PAGER (defaults to less -R)")]
+        pager: bool,
+        #[arg(
+            long,
+            value_parser = parse_format_template,
+            help = "Print each service using a template, e.g. \"{name}\\t{status}\" ({name}, {status}, {pid}, {type})"
+        )]
+        format: Option<String>,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "pager", "resources", "sort", "columns", "json", "age"],
+            help = "Stream one JSON object per service (ndjson) as they are parsed"
+        )]
+        json_lines: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "pager", "json_lines"],
+            help = "Print only service labels, one per line"
+        )]
+        label_only: bool,
+        #[arg(
+            long,
+            requires = "label_only",
+            help = "Separate labels with NUL instead of newlines (for xargs -0)"
+        )]
+        print0: bool,
+        #[arg(long, help = "Show CPU and memory usage of running services")]
+        resources: bool,
+        #[arg(long, value_enum, help = "Sort services by the given key")]
+        sort: Option<SortKey>,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Show at most N services (after sorting and filtering); with --json, \"showing N of M\" goes to stderr"
+        )]
+        limit: Option<usize>,
+        #[arg(
+            long,
+            help = "Show only services with a nonzero last exit code or brew error status"
+        )]
+        only_failed: bool,
+        #[arg(
+            long,
+            requires = "brew",
+            help = "Also list brew formulae that have a service but were never started"
+        )]
+        include_available: bool,
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            conflicts_with_all = ["format", "label_only"],
+            help = "Print an aligned table with these columns, in this order"
+        )]
+        columns: Vec<Column>,
+        #[arg(
+            long,
+            value_enum,
+            requires = "json",
+            help = "Group the JSON output into an object keyed by this field"
+        )]
+        group_by: Option<GroupBy>,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "pager", "label_only", "columns", "json_lines", "json"],
+            help = "Stable tab-separated name, status, pid, type lines for scripts"
+        )]
+        porcelain: bool,
+        #[arg(
+            long,
+            help = "Print only the service rows, without the banner, separators and totals"
+        )]
+        no_header: bool,
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = parse_duration,
+            help = "Show only services whose process started within DURATION (10m, 2h, 1d)"
+        )]
+        age: Option<Duration>,
+        #[arg(
+            long,
+            help = "Show only services whose process came up within --boot-window of system boot"
+        )]
+        boot_only: bool,
+        #[arg(
+            long,
+            value_name = "SECS",
+            default_value_t = BOOT_WINDOW_SECS,
+            help = "Seconds after boot during which a process counts as started at boot"
+        )]
+        boot_window: u64,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            value_parser = parse_name_pattern,
+            help = "Hide services whose name contains PATTERN, or matches re:<regex> (repeatable)"
+        )]
+        exclude: Vec<NamePattern>,
+        #[arg(
+            long,
+            visible_alias = "all-system",
+            help = "Also show com.apple.* services, which are hidden by default (--exclude still applies)"
+        )]
+        include_system_apple: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "pager", "label_only", "columns", "json_lines", "porcelain", "limit"],
+            help = "Print running/stopped gauges in Prometheus textfile format (with --json: counts and ratio)"
+        )]
+        metric: bool,
     },
     Start {
+        #[arg(value_parser = parse_service_name, help = "Only offer services whose name contains this pattern")]
+        pattern: Option<String>,
         #[arg(short, long, help = "Include brew services")]
         brew: bool,
+        #[arg(
+            long,
+            value_name = "SECS",
+            help = "Wait until the service is running, failing after SECS seconds"
+        )]
+        wait: Option<u64>,
+        #[arg(
+            long,
+            value_name = "ATTEMPTS",
+            requires = "wait",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Retry the start and --wait up to ATTEMPTS times until the service is running"
+        )]
+        retry_until_running: Option<u32>,
+        #[arg(
+            long,
+            requires = "pattern",
+            help = "Treat the pattern as an exact name and succeed if it is already running"
+        )]
+        if_needed: bool,
+        #[arg(
+            long,
+            conflicts_with = "if_needed",
+            help = "Pick several services at once from a checklist"
+        )]
+        multi: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["if_needed", "multi"],
+            help = "Act on every service matching the pattern (or --filter) without picking"
+        )]
+        all: bool,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            conflicts_with = "pattern",
+            requires = "all",
+            value_parser = parse_service_name,
+            help = "Pattern for --all, same as the positional pattern"
+        )]
+        filter: Option<String>,
+        #[arg(
+            long,
+            requires = "all",
+            help = "Skip the confirmation before acting on --all"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            help = "Re-check the service afterwards and fail if the change did not take effect"
+        )]
+        verify: bool,
+        #[arg(
+            last = true,
+            value_name = "ARGS",
+            help = "Extra arguments appended to the underlying launchctl/brew call (unsupported escape hatch)"
+        )]
+        extra_args: Vec<String>,
     },
     Stop {
+        #[arg(value_parser = parse_service_name, help = "Only offer services whose name contains this pattern")]
+        pattern: Option<String>,
         #[arg(short, long, help = "Include brew services")]
         brew: bool,
+        #[arg(
+            long,
+            requires = "pattern",
+            help = "Treat the pattern as an exact name and succeed if it is already stopped"
+        )]
+        if_needed: bool,
+        #[arg(
+            long,
+            value_name = "SECS",
+            help = "Keep watching for SECS seconds and fail if the service comes back (KeepAlive relaunching it)"
+        )]
+        wait: Option<u64>,
+        #[arg(
+            long,
+            conflicts_with = "if_needed",
+            help = "Pick several services at once from a checklist"
+        )]
+        multi: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["if_needed", "multi"],
+            help = "Act on every service matching the pattern (or --filter) without picking"
+        )]
+        all: bool,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            conflicts_with = "pattern",
+            requires = "all",
+            value_parser = parse_service_name,
+            help = "Pattern for --all, same as the positional pattern"
+        )]
+        filter: Option<String>,
+        #[arg(
+            long,
+            requires = "all",
+            help = "Skip the confirmation before acting on --all"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            help = "Re-check the service afterwards and fail if the change did not take effect"
+        )]
+        verify: bool,
+        #[arg(
+            last = true,
+            value_name = "ARGS",
+            help = "Extra arguments appended to the underlying launchctl/brew call (unsupported escape hatch)"
+        )]
+        extra_args: Vec<String>,
     },
     Status {
-        #[arg(help = "Service name to check status")]
-        service: String,
+        #[arg(
+            value_parser = parse_service_name,
+            help = "Service name, unique prefix or substring to check status (pick interactively when omitted)"
+        )]
+        service: Option<String>,
         #[arg(short, long, help = "Check as brew service")]
         brew: bool,
+        #[arg(
+            long,
+            help = "Pick the first match alphabetically when the name is ambiguous"
+        )]
+        first_match: bool,
+        #[arg(
+            short,
+            long,
+            requires = "service",
+            help = "Keep polling and print a line whenever the status changes"
+        )]
+        watch: bool,
+        #[arg(
+            short,
+            long,
+            visible_alias = "refresh-interval",
+            default_value = "2",
+            value_parser = parse_watch_interval,
+            help = "Time between polls in watch mode, e.g. 2, 5s or 1m (0 checks once)"
+        )]
+        interval: Duration,
+        #[arg(
+            long,
+            requires = "watch",
+            help = "Show a desktop notification when the service stops or errors"
+        )]
+        notify: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["watch", "first_match"],
+            requires = "service",
+            help = "Print only a status glyph and the name (for prompts and status bars); exit 1 unless running"
+        )]
+        oneline: bool,
+    },
+    Check {
+        #[arg(
+            required = true,
+            value_parser = parse_service_name,
+            help = "Services that must all be running"
+        )]
+        services: Vec<String>,
+        #[arg(short, long, help = "Also look the names up among brew services")]
+        brew: bool,
+    },
+    Top {
+        #[arg(
+            long,
+            value_enum,
+            default_value = "cpu",
+            help = "Sort services by the given key"
+        )]
+        sort: SortKey,
+        #[arg(
+            short,
+            long,
+            visible_alias = "refresh-interval",
+            default_value = "2",
+            value_parser = parse_watch_interval,
+            help = "Time between refreshes, e.g. 2, 5s or 1m (0 draws once)"
+        )]
+        interval: Duration,
+    },
+    Info {
+        #[arg(value_parser = parse_service_name, help = "Service name to inspect")]
+        service: String,
+        #[arg(short, long, help = "Inspect as brew service")]
+        brew: bool,
+        #[arg(long, help = "Show environment values that look like secrets")]
+        show_secrets: bool,
+        #[arg(
+            long,
+            value_name = "KEY_PATH",
+            help = "Print only this plist key, using dots for nested dicts (e.g. EnvironmentVariables.PATH)"
+        )]
+        key: Option<String>,
+    },
+    #[command(group(clap::ArgGroup::new("streaming").args(["follow", "tail"]).multiple(true)))]
+    Logs {
+        #[arg(value_parser = parse_service_name, help = "Service name to show logs for")]
+        service: String,
+        #[arg(
+            short,
+            long,
+            default_value = "1h",
+            value_parser = parse_log_window,
+            help = "Show logs since a duration (30m, 2h, 1d) or timestamp (YYYY-MM-DD [HH:MM[:SS]])"
+        )]
+        since: LogWindow,
+        #[arg(
+            short,
+            long,
+            conflicts_with = "since",
+            help = "Stream new log entries as they arrive (log stream)"
+        )]
+        follow: bool,
+        #[arg(
+            long,
+            conflicts_with = "since",
+            help = "Follow the plist's StandardOutPath/StandardErrorPath with tail -F, prefixing lines with [out]/[err] (falls back to log stream)"
+        )]
+        tail: bool,
+        #[arg(
+            long = "for",
+            value_name = "DURATION",
+            requires = "streaming",
+            value_parser = parse_duration,
+            help = "Stop following after DURATION (30s, 5m) and exit cleanly"
+        )]
+        for_duration: Option<Duration>,
+    },
+    Restart {
+        #[arg(value_parser = parse_service_name, help = "Service name to restart")]
+        service: String,
+        #[arg(short, long, help = "Restart a brew service")]
+        brew: bool,
+    },
+    Reload {
+        #[arg(value_parser = parse_service_name, help = "Launchd label or plist path to reload")]
+        service: String,
+        #[arg(
+            long,
+            help = "Watch the plist and reload on every change (Ctrl-C to stop)"
+        )]
+        watch: bool,
+    },
+    Enable {
+        #[arg(
+            required_unless_present = "from_file",
+            value_parser = parse_service_name,
+            help = "Launchd label to enable"
+        )]
+        service: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "service",
+            help = "Read labels to enable from a file, one per line"
+        )]
+        from_file: Option<PathBuf>,
+    },
+    Disable {
+        #[arg(
+            required_unless_present = "from_file",
+            value_parser = parse_service_name,
+            help = "Launchd label to disable"
+        )]
+        service: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "service",
+            help = "Read labels to disable from a file, one per line"
+        )]
+        from_file: Option<PathBuf>,
+    },
+    Lint,
+    Search {
+        #[arg(help = "Case-insensitive text to look for in plist labels and file names")]
+        pattern: String,
+    },
+    Doctor,
+    #[command(about = "Report which tools, macOS version and launchd domain this tool would use")]
+    Probe,
+    #[command(
+        about = "Print the version, subcommands, macOS version and available backends (same as --version --json)"
+    )]
+    Capabilities,
+    Completions {
+        #[arg(value_enum, help = "Shell to generate the completion script for")]
+        shell: clap_complete::Shell,
+    },
+    #[command(name = "__complete-services", hide = true)]
+    CompleteServices {
+        #[arg(short, long)]
+        brew: bool,
+    },
+    Export {
+        #[arg(help = "JSON file to write the service snapshot to")]
+        path: PathBuf,
+        #[arg(short, long, help = "Include brew services")]
+        brew: bool,
+    },
+    Import {
+        #[arg(help = "JSON snapshot written by `export`")]
+        path: PathBuf,
+        #[arg(
+            long,
+            help = "Enable, disable and start services to match the snapshot"
+        )]
+        apply: bool,
+    },
+    Cleanup {
+        #[arg(short, long, help = "Remove orphaned plists without asking")]
+        yes: bool,
+    },
+    Group {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Start the services a Brewfile marks with restart_service/start_service"
+        )]
+        brewfile: PathBuf,
+        #[arg(short, long, help = "Start them without asking")]
+        yes: bool,
+    },
+    Apply {
+        #[arg(help = "JSON file with an array of operations (reads stdin when omitted)")]
+        file: Option<PathBuf>,
+    },
+    New {
+        #[arg(help = "Reverse-DNS label for the new agent, e.g. com.example.worker")]
+        label: String,
+        #[arg(help = "Program to run")]
+        program: String,
+        #[arg(trailing_var_arg = true, help = "Arguments passed to the program")]
+        args: Vec<String>,
+        #[arg(short, long, help = "Overwrite an existing plist")]
+        force: bool,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Service {
-    name: String,
-    status: String,
-    pid: Option<String>,
-    service_type: String,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SortKey {
+    Name,
+    Status,
+    Cpu,
+    Mem,
 }
 
-struct ServiceManager {
-    brew_available: bool,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GroupBy {
+    Type,
 }
 
-impl ServiceManager {
-    fn new() -> Self {
-        let brew_available = Self::check_brew_availability();
-        Self { brew_available }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Column {
+    Name,
+    Status,
+    Pid,
+    Type,
+    Uptime,
+    Cpu,
+}
 
-    fn check_brew_availability() -> bool {
-        Command::new("which")
-            .arg("brew")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "NAME",
+            Column::Status => "STATUS",
+            Column::Pid => "PID",
+            Column::Type => "TYPE",
+            Column::Uptime => "UPTIME",
+            Column::Cpu => "CPU",
+        }
     }
+}
 
-    async fn list_launchd_services(
-        &self,
-        running_only: bool,
-    ) -> Result<Vec<Service>, Box<dyn std::error::Error>> {
-        let output = Command::new("launchctl").arg("list").output()?;
+fn format_uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {}s", seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
 
-        let output_str = str::from_utf8(&output.stdout)?;
-        let mut services = Vec::new();
+fn sort_services(services: &mut [Service], key: SortKey) {
+    // `sort_by` is stable; ties always fall back to the name so repeated runs diff cleanly.
+    services.sort_by(|a, b| {
+        let primary = match key {
+            SortKey::Name => std::cmp::Ordering::Equal,
+            SortKey::Status => a.status.sort_rank().cmp(&b.status.sort_rank()),
+            SortKey::Cpu => b
+                .cpu_percent
+                .unwrap_or(0.0)
+                .total_cmp(&a.cpu_percent.unwrap_or(0.0)),
+            SortKey::Mem => b.rss_kb.unwrap_or(0).cmp(&a.rss_kb.unwrap_or(0)),
+        };
+        primary.then_with(|| a.name.cmp(&b.name))
+    });
+}
 
-        for line in output_str.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let pid = if parts[0] == "-" {
-                    None
-                } else {
-                    Some(parts[0].to_string())
-                };
-                let status = if pid.is_some() { "running" } else { "stopped" };
-                let name = parts[2].to_string();
+#[derive(Debug, Clone)]
+enum LogWindow {
+    Last(Duration),
+    Start(String),
+}
 
-                if !running_only || status == "running" {
-                    services.push(Service {
-                        name,
-                        status: status.to_string(),
-                        pid,
-                        service_type: "launchd".to_string(),
-                    });
-                }
-            }
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}', expected e.g. 30s, 30m, 2h or 1d"))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in '{value}', expected one of s, m, h, d"
+            ))
         }
+    };
+    amount
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("invalid duration '{value}', expected e.g. 30s, 30m, 2h or 1d"))
+}
 
-        Ok(services)
+fn parse_watch_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let interval = if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        value
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| format!("invalid interval '{value}'"))?
+    } else {
+        parse_duration(value)?
+    };
+    if interval.is_zero() {
+        return Ok(interval);
+    }
+    if interval < MIN_WATCH_INTERVAL {
+        return Err("interval must be at least 1s (or 0 for a single check)".to_string());
+    }
+    if interval > MAX_WATCH_INTERVAL {
+        return Err(format!("interval '{value}' is too long, the maximum is 1d"));
     }
+    Ok(interval)
+}
 
-    async fn list_brew_services(
-        &self,
-        running_only: bool,
-    ) -> Result<Vec<Service>, Box<dyn std::error::Error>> {
-        if !self.brew_available {
-            return Ok(Vec::new());
-        }
+fn is_timestamp(value: &str) -> bool {
+    let shape_matches = |part: &str, pattern: &str| {
+        part.len() == pattern.len()
+            && part.chars().zip(pattern.chars()).all(|(c, p)| match p {
+                'd' => c.is_ascii_digit(),
+                _ => c == p,
+            })
+    };
 
-        let output = Command::new("brew").arg("services").arg("list").output()?;
+    let mut parts = value.splitn(2, ' ');
+    let date = parts.next().unwrap_or_default();
+    if !shape_matches(date, "dddd-dd-dd") {
+        return false;
+    }
+    match parts.next() {
+        None => true,
+        Some(time) => shape_matches(time, "dd:dd") || shape_matches(time, "dd:dd:dd"),
+    }
+}
 
-        let output_str = str::from_utf8(&output.stdout)?;
-        let mut services = Vec::new();
+fn parse_log_window(value: &str) -> Result<LogWindow, String> {
+    let value = value.trim();
+    if is_timestamp(value) {
+        return Ok(LogWindow::Start(value.to_string()));
+    }
+    parse_duration(value).map(LogWindow::Last).map_err(|_| {
+        format!(
+            "invalid --since value '{value}', expected a duration (30m, 2h, 1d) or a timestamp (YYYY-MM-DD [HH:MM[:SS]])"
+        )
+    })
+}
 
-        for line in output_str.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let name = parts[0].to_string();
-                let status = parts[1].to_string();
+#[derive(Debug, Clone)]
+enum NamePattern {
+    Substring(String),
+    Regex(regex::Regex),
+}
 
-                if !running_only || status == "started" {
-                    services.push(Service {
-                        name,
-                        status,
-                        pid: None,
-                        service_type: "brew".to_string(),
-                    });
-                }
-            }
+impl NamePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Substring(pattern) => name.contains(pattern.as_str()),
+            NamePattern::Regex(regex) => regex.is_match(name),
         }
-
-        Ok(services)
     }
+}
 
-    async fn start_service(
-        &self,
-        service_name: &str,
-        is_brew: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if is_brew {
-            if !self.brew_available {
-                return Err("Brew is not available".into());
-            }
-            let output = Command::new("brew")
-                .arg("services")
-                .arg("start")
-                .arg(service_name)
-                .output()?;
-
-            if output.status.success() {
-                println!(
-                    "{}",
-                    format!("✅ Brew service '{service_name}' started").green()
-                );
-            } else {
-                let error = str::from_utf8(&output.stderr)?;
-                return Err(format!("Failed to start service: {error}").into());
-            }
-        } else {
-            let output = Command::new("launchctl")
-                .arg("load")
-                .arg("-w")
-                .arg(service_name)
-                .output()?;
+// Filter criteria for the list methods. The defaults match every service; process-age filters
+// need a batched ps lookup over the whole list, so those stay with the caller.
+#[derive(Debug, Clone, Default)]
+struct ServiceQuery {
+    running_only: bool,
+    failed_only: bool,
+    hide_available: bool,
+    hide_system_apple: bool,
+    pattern: Option<String>,
+    exclude: Vec<NamePattern>,
+}
 
-            if output.status.success() {
-                println!(
-                    "{}",
-                    format!("✅ Launchd service '{service_name}' started").green()
-                );
-            } else {
-                let error = str::from_utf8(&output.stderr)?;
-                return Err(format!("Failed to start service: {error}").into());
-            }
-        }
-        Ok(())
+impl ServiceQuery {
+    fn all() -> Self {
+        Self::default()
     }
 
-    async fn stop_service(
-        &self,
-        service_name: &str,
-        is_brew: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if is_brew {
-            if !self.brew_available {
-                return Err("Brew is not available".into());
-            }
-            let output = Command::new("brew")
-                .arg("services")
-                .arg("stop")
-                .arg(service_name)
-                .output()?;
-
-            if output.status.success() {
-                println!(
-                    "{}",
-                    format!("🛑 Brew service '{service_name}' stopped").red()
-                );
-            } else {
-                let error = str::from_utf8(&output.stderr)?;
-                return Err(format!("Failed to stop service: {error}").into());
-            }
-        } else {
-            let output = Command::new("launchctl")
-                .arg("unload")
-                .arg("-w")
-                .arg(service_name)
-                .output()?;
+    fn running() -> Self {
+        Self::all().running_only(true)
+    }
 
-            if output.status.success() {
-                println!(
-                    "{}",
-                    format!("🛑 Launchd service '{service_name}' stopped").red()
-                );
-            } else {
-                let error = str::from_utf8(&output.stderr)?;
-                return Err(format!("Failed to stop service: {error}").into());
-            }
-        }
-        Ok(())
+    fn running_only(mut self, running_only: bool) -> Self {
+        self.running_only = running_only;
+        self
     }
 
-    async fn get_service_status(
-        &self,
-        service_name: &str,
-        is_brew: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if is_brew {
-            if !self.brew_available {
-                return Err("Brew is not available".into());
-            }
-            let services = self.list_brew_services(false).await?;
-            if let Some(service) = services.iter().find(|s| s.name == service_name) {
-                let status_color = match service.status.as_str() {
-                    "started" => service.status.green(),
-                    "stopped" => service.status.red(),
-                    _ => service.status.yellow(),
-                };
-                println!(
-                    "📋 Brew Service: {} - Status: {}",
-                    service.name.blue(),
-                    status_color
-                );
-            } else {
-                println!(
-                    "{}",
-                    format!("❌ Brew service '{service_name}' not found").red()
-                );
-            }
-        } else {
-            let services = self.list_launchd_services(false).await?;
-            if let Some(service) = services.iter().find(|s| s.name == service_name) {
-                let status_color = match service.status.as_str() {
-                    "running" => service.status.green(),
-                    "stopped" => service.status.red(),
-                    _ => service.status.yellow(),
-                };
-                let pid_info = service
-                    .pid
-                    .as_ref()
-                    .map_or("N/A".to_string(), |p| p.clone());
-                println!(
-                    "📋 Launchd Service: {} - Status: {} - PID: {}",
-                    service.name.blue(),
-                    status_color,
-                    pid_info.cyan()
-                );
-            } else {
-                println!(
-                    "{}",
-                    format!("❌ Launchd service '{service_name}' not found").red()
-                );
-            }
-        }
-        Ok(())
+    fn failed_only(mut self, failed_only: bool) -> Self {
+        self.failed_only = failed_only;
+        self
     }
 
-    async fn interactive_start_service(
-        &self,
-        include_brew: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let mut all_services = Vec::new();
+    fn hide_available(mut self, hide: bool) -> Self {
+        self.hide_available = hide;
+        self
+    }
 
-        let launchd_services = self.list_launchd_services(false).await?;
-        all_services.extend(launchd_services);
+    fn hide_system_apple(mut self, hide: bool) -> Self {
+        self.hide_system_apple = hide;
+        self
+    }
 
-        if include_brew && self.brew_available {
-            let brew_services = self.list_brew_services(false).await?;
-            all_services.extend(brew_services);
-        }
+    fn pattern(mut self, pattern: Option<&str>) -> Self {
+        self.pattern = pattern.map(str::to_string);
+        self
+    }
 
-        let stopped_services: Vec<&Service> = all_services
-            .iter()
-            .filter(|s| {
-                s.status == "stopped" || (s.service_type == "brew" && s.status != "started")
-            })
-            .collect();
+    fn exclude(mut self, exclude: Vec<NamePattern>) -> Self {
+        self.exclude = exclude;
+        self
+    }
 
-        if stopped_services.is_empty() {
-            println!("{}", "✅ All services are already running!".green());
-            return Ok(());
-        }
+    fn excludes(&self, service: &Service) -> bool {
+        self.exclude.iter().any(|p| p.matches(&service.name))
+    }
 
-        let service_names: Vec<String> = stopped_services
-            .iter()
-            .map(|s| format!("{} [{}]", s.name, s.service_type.to_uppercase()))
-            .collect();
+    fn hides_apple(&self, service: &Service) -> bool {
+        self.hide_system_apple && service.name.starts_with(SYSTEM_APPLE_PREFIX)
+    }
 
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("🚀 Select the service you want to start:")
-            .items(&service_names)
-            .interact()?;
+    fn matches(&self, service: &Service) -> bool {
+        (!self.running_only || service.status == ServiceStatus::Running)
+            && (!self.failed_only || service.has_failed())
+            && (!self.hide_available || service.status != ServiceStatus::Available)
+            && self
+                .pattern
+                .as_deref()
+                .is_none_or(|p| service.name.contains(p))
+            && !self.excludes(service)
+            && !self.hides_apple(service)
+    }
+}
 
-        let selected_service = stopped_services[selection];
-        let is_brew = selected_service.service_type == "brew";
+fn parse_name_pattern(value: &str) -> Result<NamePattern, String> {
+    match value.strip_prefix("re:") {
+        Some(pattern) => regex::Regex::new(pattern)
+            .map(NamePattern::Regex)
+            .map_err(|err| format!("invalid regex: {err}")),
+        None if value.is_empty() => Err("pattern must not be empty".to_string()),
+        None => Ok(NamePattern::Substring(value.to_string())),
+    }
+}
 
-        self.start_service(&selected_service.name, is_brew).await
+fn parse_service_name(value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        return Err("service name must not be empty".to_string());
+    }
+    // Names never reach a shell, so only characters no label can carry are rejected.
+    if let Some(c) = value.chars().find(|c| c.is_control()) {
+        return Err(format!("service name contains invalid character {c:?}"));
     }
+    Ok(value.to_string())
+}
 
-    async fn interactive_stop_service(
-        &self,
-        include_brew: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let mut all_services = Vec::new();
+const FORMAT_PLACEHOLDERS: [&str; 4] = ["name", "status", "pid", "type"];
 
-        let launchd_services = self.list_launchd_services(false).await?;
-        all_services.extend(launchd_services);
+enum TemplatePart<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
 
-        if include_brew && self.brew_available {
-            let brew_services = self.list_brew_services(false).await?;
-            all_services.extend(brew_services);
+fn scan_format_template(template: &str) -> Result<Vec<TemplatePart<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed '{{' in format template '{template}'"))?;
+        let placeholder = &rest[start + 1..start + end];
+        if !FORMAT_PLACEHOLDERS.contains(&placeholder) {
+            let valid: Vec<String> = FORMAT_PLACEHOLDERS
+                .iter()
+                .map(|p| format!("{{{p}}}"))
+                .collect();
+            return Err(format!(
+                "unknown placeholder '{{{placeholder}}}', valid placeholders are: {}",
+                valid.join(", ")
+            ));
         }
+        parts.push(TemplatePart::Literal(&rest[..start]));
+        parts.push(TemplatePart::Placeholder(placeholder));
+        rest = &rest[start + end + 1..];
+    }
+    parts.push(TemplatePart::Literal(rest));
+    Ok(parts)
+}
 
-        let running_services: Vec<&Service> = all_services
-            .iter()
-            .filter(|s| s.status == "running" || s.status == "started")
-            .collect();
+fn parse_format_template(value: &str) -> Result<String, String> {
+    scan_format_template(value)?;
+    Ok(value.replace("\\t", "\t").replace("\\n", "\n"))
+}
 
-        if running_services.is_empty() {
-            println!("{}", "🛑 No running services found!".red());
-            return Ok(());
+// One pass, so a value that itself contains "{status}" (a label, say) is never expanded again.
+fn render_format_template(template: &str, service: &Service) -> String {
+    let Ok(parts) = scan_format_template(template) else {
+        return template.to_string();
+    };
+    let mut rendered = String::with_capacity(template.len());
+    for part in parts {
+        match part {
+            TemplatePart::Literal(text) => rendered.push_str(text),
+            TemplatePart::Placeholder("name") => rendered.push_str(&service.name),
+            TemplatePart::Placeholder("status") => rendered.push_str(&service.status.to_string()),
+            TemplatePart::Placeholder("pid") => {
+                rendered.push_str(service.pid.as_deref().unwrap_or(""))
+            }
+            // The scanner only lets FORMAT_PLACEHOLDERS through, so this is {type}.
+            TemplatePart::Placeholder(_) => rendered.push_str(&service.service_type.to_string()),
         }
+    }
+    rendered
+}
 
-        let service_names: Vec<String> = running_services
-            .iter()
-            .map(|s| {
-                let pid_info = s
-                    .pid
-                    .as_ref()
-                    .map_or("".to_string(), |p| format!(" (PID: {p})"));
-                format!("{} [{}]{}", s.name, s.service_type.to_uppercase(), pid_info)
-            })
-            .collect();
-
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("🛑 Select the service you want to stop:")
-            .items(&service_names)
-            .interact()?;
-
-        let selected_service = running_services[selection];
-        let is_brew = selected_service.service_type == "brew";
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ServiceStatus {
+    Running,
+    Stopped,
+    #[serde(rename = "error")]
+    Errored,
+    Scheduled,
+    Disabled,
+    Available,
+    // Untagged so an unrecognised status is still a plain string in JSON, like the others.
+    #[serde(untagged)]
+    Unknown(String),
+}
 
-        self.stop_service(&selected_service.name, is_brew).await
+impl ServiceStatus {
+    fn from_launchd(pid: Option<&str>) -> Self {
+        match pid {
+            Some(_) => ServiceStatus::Running,
+            None => ServiceStatus::Stopped,
+        }
     }
 
-    fn print_services(&self, services: &[Service]) {
+    fn from_brew(status: &str) -> Self {
+        match status {
+            "started" => ServiceStatus::Running,
+            "stopped" => ServiceStatus::Stopped,
+            "none" => ServiceStatus::Available,
+            "error" => ServiceStatus::Errored,
+            "scheduled" => ServiceStatus::Scheduled,
+            other => ServiceStatus::Unknown(other.to_string()),
+        }
+    }
+
+    fn sort_rank(&self) -> u8 {
+        match self {
+            ServiceStatus::Running => 0,
+            ServiceStatus::Errored => 1,
+            ServiceStatus::Scheduled => 2,
+            ServiceStatus::Stopped => 3,
+            ServiceStatus::Disabled => 4,
+            ServiceStatus::Available => 5,
+            ServiceStatus::Unknown(_) => 6,
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            ServiceStatus::Running => icon(Icon::Running),
+            ServiceStatus::Stopped | ServiceStatus::Errored => icon(Icon::Stopped),
+            ServiceStatus::Disabled => icon(Icon::Disabled),
+            ServiceStatus::Available => icon(Icon::Available),
+            _ => icon(Icon::Other),
+        }
+    }
+
+    fn colored(&self) -> ColoredString {
+        let text = self.to_string();
+        match self {
+            ServiceStatus::Running => text.green(),
+            ServiceStatus::Stopped | ServiceStatus::Errored => text.red(),
+            ServiceStatus::Disabled => text.dimmed(),
+            ServiceStatus::Available => text.blue(),
+            _ => text.yellow(),
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceStatus::Running => write!(f, "running"),
+            ServiceStatus::Stopped => write!(f, "stopped"),
+            ServiceStatus::Errored => write!(f, "error"),
+            ServiceStatus::Scheduled => write!(f, "scheduled"),
+            ServiceStatus::Disabled => write!(f, "disabled"),
+            ServiceStatus::Available => write!(f, "available"),
+            ServiceStatus::Unknown(status) => write!(f, "{status}"),
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+enum ServiceType {
+    #[default]
+    Launchd,
+    Brew,
+}
+
+impl std::fmt::Display for ServiceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceType::Launchd => write!(f, "launchd"),
+            ServiceType::Brew => write!(f, "brew"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Service {
+    name: String,
+    status: ServiceStatus,
+    pid: Option<String>,
+    service_type: ServiceType,
+    last_exit_code: Option<i32>,
+    cpu_percent: Option<f32>,
+    rss_kb: Option<u64>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    plist_path: Option<PathBuf>,
+    #[serde(default)]
+    user: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BrewServiceEntry {
+    name: String,
+    status: String,
+    #[serde(default)]
+    exit_code: Option<i32>,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    user: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ApplyAction {
+    Start,
+    Stop,
+    Enable,
+    Disable,
+    Reload,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApplyOperation {
+    action: ApplyAction,
+    name: String,
+    #[serde(rename = "type", default)]
+    service_type: ServiceType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct MacosVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl MacosVersion {
+    const LAUNCHD_REWRITE: MacosVersion = MacosVersion {
+        major: 10,
+        minor: 10,
+    };
+
+    fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+        Some(MacosVersion { major, minor })
+    }
+
+    fn detect() -> Option<Self> {
+        let output = Command::new("sw_vers")
+            .arg("-productVersion")
+            .traced_output()
+            .ok()?;
+        Self::parse(str::from_utf8(&output.stdout).ok()?)
+    }
+
+    // bootstrap/bootout, print, blame and managername all arrived with the 10.10 launchd rewrite.
+    fn supports_bootstrap(&self) -> bool {
+        *self >= Self::LAUNCHD_REWRITE
+    }
+
+    fn supports_print(&self) -> bool {
+        *self >= Self::LAUNCHD_REWRITE
+    }
+}
+
+impl std::fmt::Display for MacosVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PickMode {
+    One,
+    Multi,
+    All { force: bool },
+}
+
+impl PickMode {
+    fn new(multi: bool, all: bool, force: bool) -> Self {
+        if all {
+            PickMode::All { force }
+        } else if multi {
+            PickMode::Multi
+        } else {
+            PickMode::One
+        }
+    }
+}
+
+enum ServiceError {
+    ServiceNotFound { name: String },
+    PlistNotFound { name: String },
+    TransientService { name: String },
+    BrewUnavailable,
+    CommandFailed { message: String, stderr: String },
+}
+
+impl ServiceError {
+    fn command_failed(message: impl Into<String>, stderr: &str) -> Self {
+        ServiceError::CommandFailed {
+            message: message.into(),
+            stderr: stderr.trim().to_string(),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ServiceError::ServiceNotFound { .. } => "ServiceNotFound",
+            ServiceError::PlistNotFound { .. } => "PlistNotFound",
+            ServiceError::TransientService { .. } => "TransientService",
+            ServiceError::BrewUnavailable => "BrewUnavailable",
+            ServiceError::CommandFailed { .. } => "CommandFailed",
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceError::ServiceNotFound { name } => write!(f, "Service '{name}' not found"),
+            ServiceError::PlistNotFound { name } => write!(f, "No plist found for '{name}'"),
+            ServiceError::TransientService { name } => write!(
+                f,
+                "'{name}' has no plist on disk; it is a transient/registered service (submitted at runtime, e.g. by an app or launchctl submit), so there is no file to read or reload"
+            ),
+            ServiceError::BrewUnavailable => write!(f, "Brew is not available"),
+            ServiceError::CommandFailed { message, stderr } => write!(f, "{message}: {stderr}"),
+        }
+    }
+}
+
+// Matches how a plain string error prints when main returns it.
+impl std::fmt::Debug for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+#[derive(Debug, Serialize)]
+struct ErrorReport {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorDetail {
+    kind: &'static str,
+    message: String,
+    stderr: Option<String>,
+}
+
+impl ErrorReport {
+    fn new(err: &(dyn std::error::Error + 'static)) -> Self {
+        let (kind, stderr) = match err.downcast_ref::<ServiceError>() {
+            Some(ServiceError::CommandFailed { stderr, .. }) => {
+                ("CommandFailed", Some(stderr.clone()))
+            }
+            Some(service_error) => (service_error.kind(), None),
+            None if err.is::<std::io::Error>() => ("Io", None),
+            None if err.is::<serde_json::Error>() => ("InvalidJson", None),
+            None => ("Other", None),
+        };
+        ErrorReport {
+            error: ErrorDetail {
+                kind,
+                message: err.to_string(),
+                stderr,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApplyResult {
+    index: usize,
+    action: ApplyAction,
+    name: String,
+    #[serde(rename = "type")]
+    service_type: ServiceType,
+    success: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ActionResult {
+    action: ApplyAction,
+    name: String,
+    #[serde(rename = "type")]
+    service_type: ServiceType,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn parse_apply_operations(input: &str) -> Result<Vec<ApplyOperation>, Box<dyn std::error::Error>> {
+    let value: serde_json::Value =
+        serde_json::from_str(input).map_err(|err| format!("Invalid JSON: {err}"))?;
+    let entries = value
+        .as_array()
+        .ok_or("Expected a JSON array of operations")?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let operation: ApplyOperation = serde_json::from_value(entry.clone())
+                .map_err(|err| format!("Malformed operation at index {index}: {err}"))?;
+            if operation.service_type == ServiceType::Brew
+                && !matches!(operation.action, ApplyAction::Start | ApplyAction::Stop)
+            {
+                return Err(format!(
+                    "Malformed operation at index {index}: brew services only support start and stop"
+                )
+                .into());
+            }
+            Ok(operation)
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LaunchAgentPlist {
+    label: String,
+    program_arguments: Vec<String>,
+    run_at_load: bool,
+    keep_alive: bool,
+}
+
+fn validate_label(label: &str) -> Result<(), String> {
+    let segments: Vec<&str> = label.split('.').collect();
+    let valid_chars = label
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if segments.len() < 2 || segments.iter().any(|s| s.is_empty()) || !valid_chars {
+        return Err(format!(
+            "'{label}' is not a reverse-DNS label (expected something like com.example.worker)"
+        ));
+    }
+    Ok(())
+}
+
+fn resolve_executable(program: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = if program.contains('/') {
+        PathBuf::from(program)
+    } else {
+        let output = Command::new("which").arg(program).traced_output()?;
+        if !output.status.success() {
+            return Err(format!("'{program}' was not found on PATH").into());
+        }
+        PathBuf::from(str::from_utf8(&output.stdout)?.trim())
+    };
+
+    let path = path
+        .canonicalize()
+        .map_err(|_| format!("'{}' does not exist", path.display()))?;
+    #[cfg(unix)]
+    let executable = std::fs::metadata(&path)?.permissions().mode() & 0o111 != 0;
+    #[cfg(not(unix))]
+    let executable = true;
+    if !path.is_file() || !executable {
+        return Err(format!("'{}' is not an executable file", path.display()).into());
+    }
+    Ok(path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LaunchDomain {
+    Gui,
+    System,
+}
+
+#[derive(Debug)]
+struct PlistLocation {
+    label: String,
+    path: PathBuf,
+    domain: LaunchDomain,
+}
+
+const PLIST_SEARCH_DIRS: [(&str, LaunchDomain); 5] = [
+    ("~/Library/LaunchAgents", LaunchDomain::Gui),
+    ("/Library/LaunchAgents", LaunchDomain::Gui),
+    ("/Library/LaunchDaemons", LaunchDomain::System),
+    ("/System/Library/LaunchAgents", LaunchDomain::Gui),
+    ("/System/Library/LaunchDaemons", LaunchDomain::System),
+];
+
+fn expand_home(dir: &str) -> PathBuf {
+    match dir.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME")
+            .map(|home| Path::new(&home).join(rest))
+            .unwrap_or_else(|_| PathBuf::from(dir)),
+        None => PathBuf::from(dir),
+    }
+}
+
+fn domain_for_path(path: &Path) -> LaunchDomain {
+    if path.to_string_lossy().contains("LaunchDaemons") {
+        LaunchDomain::System
+    } else {
+        LaunchDomain::Gui
+    }
+}
+
+fn plist_kind(path: &Path) -> &'static str {
+    let path = path.to_string_lossy();
+    if path.starts_with("/System/Library/LaunchDaemons") {
+        "Apple system daemon"
+    } else if path.starts_with("/System/Library/LaunchAgents") {
+        "Apple agent"
+    } else if path.starts_with("/Library/LaunchDaemons") {
+        "system daemon"
+    } else if path.starts_with("/Library/LaunchAgents") {
+        "agent for all users"
+    } else if path.contains("LaunchDaemons") {
+        "daemon"
+    } else {
+        "user agent"
+    }
+}
+
+const BOOT_WINDOW_SECS: u64 = 300;
+
+const SYSTEM_APPLE_PREFIX: &str = "com.apple.";
+
+// `sysctl -n kern.boottime` prints e.g. `{ sec = 1715000000, usec = 123456 } Mon May  6 ...`.
+fn parse_boottime(output: &str) -> Option<u64> {
+    let rest = &output[output.find("sec = ")? + "sec = ".len()..];
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn seconds_since_boot_at_start(boot_time: u64, uptime_secs: u64) -> Option<u64> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    now.checked_sub(uptime_secs)?.checked_sub(boot_time)
+}
+
+fn parse_etime(value: &str) -> Option<u64> {
+    let (days, clock) = match value.trim().split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, value.trim()),
+    };
+    let mut seconds = 0;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(days * 24 * 60 * 60 + seconds)
+}
+
+const BREW_PREFIX_PATHS: [&str; 2] = ["/opt/homebrew/bin/brew", "/usr/local/bin/brew"];
+
+fn describe_command(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| {
+            let part = part.to_string_lossy();
+            if part.is_empty() || part.contains(|c: char| c.is_whitespace() || c == '"') {
+                format!("'{part}'")
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Runs `op` over every item with at most `limit` in flight, returning results in input order.
+async fn run_limited<'a, T, F, Fut>(items: &'a [T], limit: usize, op: F) -> Vec<Fut::Output>
+where
+    F: Fn(&'a T) -> Fut,
+    Fut: std::future::Future,
+{
+    let semaphore = tokio::sync::Semaphore::new(limit.max(1));
+    join_all(items.iter().map(|item| {
+        let semaphore = &semaphore;
+        let op = &op;
+        async move {
+            let _permit = semaphore
+                .acquire()
+                .await
+                .expect("semaphore is never closed");
+            op(item).await
+        }
+    }))
+    .await
+}
+
+trait TracedCommand {
+    fn traced_output(&mut self) -> std::io::Result<Output>;
+    fn traced_status(&mut self) -> std::io::Result<ExitStatus>;
+    async fn traced_output_async(&mut self) -> std::io::Result<Output>;
+}
+
+// sudo resets the environment by default, so explicit variables (brew's opt-outs) are passed
+// through env(1) instead of relying on the sudoers env_keep list.
+fn with_sudo(command: &Command) -> Command {
+    let mut elevated = Command::new("sudo");
+    elevated.arg("--");
+    let envs: Vec<String> = command
+        .get_envs()
+        .filter_map(|(key, value)| {
+            Some(format!(
+                "{}={}",
+                key.to_string_lossy(),
+                value?.to_string_lossy()
+            ))
+        })
+        .collect();
+    if !envs.is_empty() {
+        elevated.arg("env").args(envs);
+    }
+    elevated.arg(command.get_program()).args(command.get_args());
+    if let Some(dir) = command.get_current_dir() {
+        elevated.current_dir(dir);
+    }
+    elevated
+}
+
+fn record_timing(command: &Command, elapsed: Duration) {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        if let Some(timings) = timings.as_mut() {
+            timings.push((describe_command(command), elapsed));
+        }
+    }
+}
+
+fn print_timings() {
+    let Some(timings) = TIMINGS.lock().ok().and_then(|mut timings| timings.take()) else {
+        return;
+    };
+    let total = STARTED.get().map_or(Duration::ZERO, Instant::elapsed);
+    let subprocess: Duration = timings.iter().map(|(_, elapsed)| *elapsed).sum();
+
+    eprintln!("{}", format!("{} Timings", icon(Icon::Time)).bold());
+    eprintln!("{:>8}  {}", "ms".dimmed(), "command".dimmed());
+    for (command, elapsed) in &timings {
+        let command: String = if command.chars().count() > 70 {
+            command
+                .chars()
+                .take(69)
+                .chain(std::iter::once('…'))
+                .collect()
+        } else {
+            command.clone()
+        };
+        eprintln!("{:>8.1}  {command}", elapsed.as_secs_f64() * 1000.0);
+    }
+    eprintln!(
+        "{:>8.1}  {}",
+        subprocess.as_secs_f64() * 1000.0,
+        format!("subprocesses ({} calls)", timings.len()).bold()
+    );
+    eprintln!("{:>8.1}  {}", total.as_secs_f64() * 1000.0, "total".bold());
+}
+
+fn exit_with(code: i32) -> ! {
+    print_timings();
+    std::process::exit(code);
+}
+
+fn trace_output(command: &Command, started: Instant, result: &std::io::Result<Output>) {
+    record_timing(command, started.elapsed());
+    match result {
+        Ok(output) => debug!(
+            command = %describe_command(command),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            status = %output.status,
+            stdout_bytes = output.stdout.len(),
+            "command finished"
+        ),
+        Err(err) => {
+            debug!(command = %describe_command(command), error = %err, "command failed to run")
+        }
+    }
+}
+
+impl TracedCommand for Command {
+    fn traced_output(&mut self) -> std::io::Result<Output> {
+        let started = Instant::now();
+        let result = self.output();
+        trace_output(self, started, &result);
+        result
+    }
+
+    // Runs on tokio's reactor so batch operations can overlap their subprocesses.
+    async fn traced_output_async(&mut self) -> std::io::Result<Output> {
+        let started = Instant::now();
+        let mut command = tokio::process::Command::new(self.get_program());
+        command.args(self.get_args());
+        for (key, value) in self.get_envs() {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+        if let Some(dir) = self.get_current_dir() {
+            command.current_dir(dir);
+        }
+        let result = command.output().await;
+        trace_output(self, started, &result);
+        result
+    }
+
+    fn traced_status(&mut self) -> std::io::Result<ExitStatus> {
+        let started = Instant::now();
+        let result = self.status();
+        record_timing(self, started.elapsed());
+        match &result {
+            Ok(status) => debug!(
+                command = %describe_command(self),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                %status,
+                "command finished"
+            ),
+            Err(err) => {
+                debug!(command = %describe_command(self), error = %err, "command failed to run")
+            }
+        }
+        result
+    }
+}
+
+fn page_output(output: &str) -> bool {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+
+    let Ok(mut child) = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's not an error.
+        let _ = writeln!(stdin, "{output}");
+    }
+    child.wait().is_ok()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LaunchctlColumns {
+    pid: usize,
+    status: usize,
+    label: usize,
+}
+
+impl LaunchctlColumns {
+    const DEFAULT: LaunchctlColumns = LaunchctlColumns {
+        pid: 0,
+        status: 1,
+        label: 2,
+    };
+
+    fn from_header(line: &str) -> Option<Self> {
+        let headers: Vec<String> = line
+            .split_whitespace()
+            .map(|h| h.to_ascii_lowercase())
+            .collect();
+        let position = |name: &str| headers.iter().position(|h| h == name);
+        Some(LaunchctlColumns {
+            pid: position("pid")?,
+            status: position("status")?,
+            label: position("label")?,
+        })
+    }
+}
+
+struct LaunchctlEntry {
+    pid: Option<String>,
+    last_exit_code: Option<i32>,
+    label: String,
+}
+
+// A few jobs are registered under a path instead of a reverse-DNS label, e.g.
+// `/Users/me/Library/LaunchAgents/com.example.foo.plist` is shown as `com.example.foo`.
+fn clean_launchd_label(label: &str) -> Option<String> {
+    if !label.contains('/') {
+        return None;
+    }
+    let file_name = Path::new(label).file_name()?.to_string_lossy();
+    let name = file_name.strip_suffix(".plist").unwrap_or(&file_name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn parse_launchctl_list(output: &str) -> impl Iterator<Item = LaunchctlEntry> + '_ {
+    let mut lines = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .peekable();
+    // Locate columns by header name so layout changes between macOS releases don't shift them.
+    let columns = match lines.peek() {
+        None => {
+            debug!("launchctl list printed nothing");
+            LaunchctlColumns::DEFAULT
+        }
+        Some(line) => match LaunchctlColumns::from_header(line) {
+            Some(columns) => {
+                lines.next();
+                if lines.peek().is_none() {
+                    debug!("launchctl list printed only its header");
+                }
+                columns
+            }
+            None => {
+                warn!(
+                    first_line = %line,
+                    "launchctl list has no PID/Status/Label header, assuming the default column order"
+                );
+                LaunchctlColumns::DEFAULT
+            }
+        },
+    };
+
+    lines.filter_map(move |line| {
+        // Columns are tab-separated, and a label may itself contain spaces.
+        let parts: Vec<&str> = if line.contains('\t') {
+            line.split('\t').map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        let pid = *parts.get(columns.pid)?;
+        let label = if columns.label == columns.pid.max(columns.status) + 1 {
+            parts.get(columns.label..)?.join(" ")
+        } else {
+            parts.get(columns.label)?.to_string()
+        };
+        if label.is_empty() {
+            return None;
+        }
+        Some(LaunchctlEntry {
+            pid: pid.parse::<u32>().is_ok().then(|| pid.to_string()),
+            last_exit_code: parts.get(columns.status).and_then(|s| s.parse().ok()),
+            label,
+        })
+    })
+}
+
+const SECRET_KEY_PATTERNS: [&str; 5] = ["TOKEN", "KEY", "SECRET", "PASSWORD", "CREDENTIAL"];
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SECRET_KEY_PATTERNS
+        .iter()
+        .any(|pattern| key.contains(pattern))
+}
+
+fn parse_print_section(output: &str, section: &str) -> Vec<(String, String)> {
+    let header = format!("{section} = {{");
+    output
+        .lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| line.trim() != "}")
+        .filter_map(|line| {
+            let (key, value) = line.split_once(" => ")?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// Top-level `key = value` fields of `launchctl print` are indented by exactly one tab.
+fn parse_print_field(output: &str, key: &str) -> Option<String> {
+    let prefix = format!("{key} = ");
+    output.lines().find_map(|line| {
+        let line = line.strip_prefix('\t').filter(|l| !l.starts_with('\t'))?;
+        let value = line.strip_prefix(&prefix)?.trim();
+        (!value.ends_with('{')).then(|| value.to_string())
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct InfoReport {
+    name: String,
+    label: String,
+    #[serde(rename = "type")]
+    service_type: ServiceType,
+    status: ServiceStatus,
+    state: Option<String>,
+    pid: Option<String>,
+    last_exit_code: Option<i32>,
+    plist_path: Option<PathBuf>,
+    transient: bool,
+    domain: Option<String>,
+    program_arguments: Option<Vec<String>>,
+    keep_alive: Option<serde_json::Value>,
+    run_at_load: Option<bool>,
+    working_directory: Option<String>,
+    environment: BTreeMap<String, String>,
+    plist: Option<serde_json::Value>,
+}
+
+impl InfoReport {
+    fn new(
+        service: &Service,
+        label: &str,
+        location: Option<&PlistLocation>,
+        printed: Option<&str>,
+        mut plist: Option<plist::Dictionary>,
+        environment: Vec<(String, String)>,
+        show_secrets: bool,
+    ) -> Self {
+        let redact = |key: &str, value: String| {
+            if !show_secrets && is_secret_key(key) {
+                "********".to_string()
+            } else {
+                value
+            }
+        };
+        if let Some(env) = plist
+            .as_mut()
+            .and_then(|plist| plist.get_mut("EnvironmentVariables")?.as_dictionary_mut())
+        {
+            for (key, value) in env.iter_mut() {
+                if let Some(text) = value.as_string() {
+                    *value = plist::Value::String(redact(key, text.to_string()));
+                }
+            }
+        }
+        let get = |key: &str| plist.as_ref().and_then(|plist| plist.get(key));
+        let program_arguments = match get("ProgramArguments") {
+            Some(plist::Value::Array(args)) => Some(
+                args.iter()
+                    .filter_map(|arg| arg.as_string().map(str::to_string))
+                    .collect(),
+            ),
+            _ => get("Program")
+                .and_then(plist::Value::as_string)
+                .map(|program| vec![program.to_string()]),
+        };
+        let print_field = |key: &str| printed.and_then(|output| parse_print_field(output, key));
+        InfoReport {
+            name: service.name.clone(),
+            label: label.to_string(),
+            service_type: service.service_type,
+            status: service.status.clone(),
+            state: print_field("state"),
+            pid: service.pid.clone(),
+            last_exit_code: print_field("last exit code")
+                .and_then(|code| code.parse().ok())
+                .or(service.last_exit_code),
+            plist_path: location.map(|location| location.path.clone()),
+            transient: location.is_none() && service.service_type == ServiceType::Launchd,
+            domain: None,
+            program_arguments,
+            keep_alive: get("KeepAlive").and_then(|value| serde_json::to_value(value).ok()),
+            run_at_load: get("RunAtLoad").and_then(plist::Value::as_boolean),
+            working_directory: get("WorkingDirectory")
+                .and_then(plist::Value::as_string)
+                .map(str::to_string),
+            environment: environment
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = redact(&key, value);
+                    (key, value)
+                })
+                .collect(),
+            plist: plist.and_then(|plist| serde_json::to_value(plist).ok()),
+        }
+    }
+}
+
+fn read_plist(path: &Path) -> Option<plist::Dictionary> {
+    plist::Value::from_file(path).ok()?.into_dictionary()
+}
+
+// launchd expects `<Label>.plist`; a mismatch makes loads fail with an unhelpful error.
+fn label_mismatch(path: &Path, label: &str) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    (stem != label).then(|| {
+        format!(
+            "{}: Label is '{label}' but the filename expects '{stem}'",
+            path.display()
+        )
+    })
+}
+
+fn session_types(plist: &plist::Dictionary) -> Vec<String> {
+    // LimitLoadToSessionType may be a single string or an array of strings.
+    match plist.get("LimitLoadToSessionType") {
+        Some(plist::Value::String(session)) => vec![session.clone()],
+        Some(plist::Value::Array(sessions)) => sessions
+            .iter()
+            .filter_map(|session| session.as_string().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn explain_blame(reason: &str) -> &'static str {
+    let reason = reason.to_lowercase();
+    if reason.contains("semaphore") || reason.contains("keepalive") {
+        "a KeepAlive condition restarts it whenever it exits"
+    } else if reason.contains("speculative") {
+        "launched when its plist was loaded (RunAtLoad or at login/boot)"
+    } else if reason.contains("ipc") || reason.contains("mach") {
+        "a client connected to one of its MachServices"
+    } else if reason.contains("calendar") {
+        "its StartCalendarInterval schedule fired"
+    } else if reason.contains("interval") {
+        "its StartInterval timer fired"
+    } else if reason.contains("event") {
+        "a LaunchEvents (XPC event) trigger fired"
+    } else if reason.contains("demand") {
+        "something requested it on demand"
+    } else if reason.contains("inefficient") {
+        "launchd started it opportunistically while the system was idle"
+    } else {
+        "launchd did not give a reason it knows how to explain"
+    }
+}
+
+// Both log predicates and AppleScript take double-quoted strings with backslash escapes.
+fn escape_quoted(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// A reload may find the job already unloaded: modern launchctl exits 3 ("No such process")
+// or 113 ("Could not find specified service"), legacy unload only says so on stderr.
+fn bootout_not_loaded(output: &Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    matches!(output.status.code(), Some(3 | 113))
+        || stderr.contains("No such process")
+        || stderr.contains("Could not find specified service")
+}
+
+fn log_predicate(service_name: &str) -> String {
+    let name = escape_quoted(service_name);
+    format!("subsystem == \"{name}\" OR process == \"{name}\" OR eventMessage CONTAINS \"{name}\"")
+}
+
+fn current_session_type() -> Option<String> {
+    let output = Command::new("launchctl")
+        .arg("managername")
+        .traced_output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+fn plist_key_path<'a>(
+    plist: &'a plist::Dictionary,
+    path: &str,
+) -> Result<&'a plist::Value, String> {
+    let mut segments = path.split('.');
+    let first = segments.next().unwrap_or_default();
+    let mut value = plist
+        .get(first)
+        .ok_or_else(|| format!("Key '{first}' not found in plist"))?;
+    let mut walked = first.to_string();
+    for segment in segments {
+        let dict = value
+            .as_dictionary()
+            .ok_or_else(|| format!("'{walked}' is not a dictionary, cannot read '{segment}'"))?;
+        value = dict
+            .get(segment)
+            .ok_or_else(|| format!("Key '{walked}.{segment}' not found in plist"))?;
+        walked = format!("{walked}.{segment}");
+    }
+    Ok(value)
+}
+
+fn format_plist_value(value: &plist::Value) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match value {
+        plist::Value::String(text) => text.clone(),
+        plist::Value::Boolean(flag) => flag.to_string(),
+        plist::Value::Integer(number) => number.to_string(),
+        plist::Value::Real(number) => number.to_string(),
+        other => serde_json::to_string_pretty(other)?,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResult {
+    label: String,
+    loaded: bool,
+    paths: Vec<PathBuf>,
+}
+
+fn print_search_results(pattern: &str, results: &[SearchResult]) {
+    if results.is_empty() {
+        println!(
+            "{}",
+            format!("{} No plists match '{pattern}'", icon(Icon::Empty)).yellow()
+        );
+        return;
+    }
+    println!(
+        "{}",
+        format!(
+            "{} {} label(s) match '{pattern}':",
+            icon(Icon::Match),
+            results.len()
+        )
+        .bold()
+    );
+    for result in results {
+        let state = if result.loaded {
+            "loaded".green()
+        } else {
+            "not loaded".dimmed()
+        };
+        let marker = if result.loaded {
+            icon(Icon::Running)
+        } else {
+            icon(Icon::Available)
+        };
+        println!("{marker} {} - {state}", result.label.bold());
+        if result.paths.len() > 1 {
+            println!(
+                "    {}",
+                format!(
+                    "{} defined in {} plists, launchd only loads one",
+                    icon(Icon::Duplicate),
+                    result.paths.len()
+                )
+                .yellow()
+            );
+        }
+        for path in &result.paths {
+            println!("    {}", path.display().to_string().dimmed());
+        }
+    }
+}
+
+struct PlistFile {
+    path: PathBuf,
+    contents: Result<plist::Dictionary, String>,
+}
+
+fn scan_plist_dirs() -> Vec<PlistFile> {
+    let mut files = Vec::new();
+    for (dir, _) in PLIST_SEARCH_DIRS {
+        let Ok(entries) = std::fs::read_dir(expand_home(dir)) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "plist"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let contents = plist::Value::from_file(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|value| {
+                    value
+                        .into_dictionary()
+                        .ok_or_else(|| "top-level value is not a dictionary".to_string())
+                });
+            files.push(PlistFile { path, contents });
+        }
+    }
+    files
+}
+
+fn plist_program(plist: &plist::Dictionary) -> Option<String> {
+    plist
+        .get("Program")
+        .and_then(|program| program.as_string())
+        .or_else(|| {
+            plist
+                .get("ProgramArguments")?
+                .as_array()?
+                .first()?
+                .as_string()
+        })
+        .map(str::to_string)
+}
+
+fn find_orphaned_brew_plists(files: &[PlistFile]) -> Vec<(PathBuf, String, String)> {
+    files
+        .iter()
+        .filter(|file| {
+            file.path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("homebrew.mxcl."))
+        })
+        .filter_map(|file| {
+            let plist = file.contents.as_ref().ok()?;
+            let label = plist.get("Label")?.as_string()?.to_string();
+            let program = plist_program(plist)?;
+            (!Path::new(&program).exists()).then(|| (file.path.clone(), label, program))
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorCheck {
+    check: &'static str,
+    passed: bool,
+    detail: String,
+    hint: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    passed: bool,
+    checks: Vec<DoctorCheck>,
+}
+
+fn doctor_check(check: &'static str, problems: Vec<String>, ok: String, hint: &str) -> DoctorCheck {
+    DoctorCheck {
+        check,
+        passed: problems.is_empty(),
+        hint: (!problems.is_empty()).then(|| hint.to_string()),
+        detail: if problems.is_empty() {
+            ok
+        } else {
+            problems.join("; ")
+        },
+    }
+}
+
+fn parse_brew_success_label(stdout: &str) -> Option<String> {
+    stdout
+        .lines()
+        .find(|line| line.contains("Successfully"))
+        .and_then(|line| line.split_once("(label: "))
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(label, _)| label.trim().to_string())
+}
+
+fn brew_label(name: &str) -> String {
+    format!("homebrew.mxcl.{name}")
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn closest_name<'a>(services: &'a [Service], query: &str) -> Option<&'a str> {
+    let threshold = (query.chars().count() / 3).max(2);
+    services
+        .iter()
+        .map(|s| (edit_distance(&s.name, query), s.name.as_str()))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, name)| name)
+}
+
+fn not_found_message(kind: &str, services: &[Service], query: &str) -> String {
+    match closest_name(services, query) {
+        Some(name) => format!(
+            "{} {kind} service '{query}' not found. Did you mean '{name}'?",
+            icon(Icon::Error)
+        ),
+        None => format!("{} {kind} service '{query}' not found", icon(Icon::Error)),
+    }
+}
+
+fn read_label_file(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+// Minimal Brewfile support: only `brew "name", restart_service: true` style lines are read.
+fn parse_brewfile(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next()?.trim();
+            let rest = line.strip_prefix("brew ")?.trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let (name, options) = rest[1..].split_once(quote)?;
+            let wants_service = options.split(',').any(|option| {
+                option.split_once(':').is_some_and(|(key, value)| {
+                    matches!(key.trim(), "restart_service" | "start_service")
+                        && value.trim() != "false"
+                })
+            });
+            // Tapped formulae are written as `user/tap/name` but their service is just `name`.
+            wants_service.then(|| name.rsplit('/').next().unwrap_or(name).to_string())
+        })
+        .collect()
+}
+
+fn parse_print_disabled(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("disabled services"))
+        .skip(1)
+        .take_while(|line| line.trim() != "}")
+        .filter_map(|line| {
+            let (label, state) = line.split_once("=>")?;
+            matches!(state.trim(), "disabled" | "true")
+                .then(|| label.trim().trim_matches('"').to_string())
+        })
+        .collect()
+}
+
+fn find_service<'a>(
+    services: &'a [Service],
+    query: &str,
+    first_match: bool,
+) -> Result<Option<&'a Service>, Box<dyn std::error::Error>> {
+    if let Some(service) = services.iter().find(|s| s.is_named(query)) {
+        return Ok(Some(service));
+    }
+
+    let mut candidates: Vec<&Service> = services
+        .iter()
+        .filter(|s| s.name.starts_with(query))
+        .collect();
+    if candidates.is_empty() {
+        candidates = services.iter().filter(|s| s.name.contains(query)).collect();
+    }
+    candidates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(candidates[0])),
+        _ if first_match => Ok(Some(candidates[0])),
+        count => {
+            // Part of the error, so it goes to stderr and leaves stdout clean for --json.
+            eprintln!(
+                "{}",
+                format!("{} '{query}' matches {count} services:", icon(Icon::Match)).yellow()
+            );
+            for candidate in &candidates {
+                eprintln!("  {}", candidate.name);
+            }
+            Err(format!("'{query}' is ambiguous, use a longer name or --first-match").into())
+        }
+    }
+}
+
+fn format_resources(service: &Service) -> String {
+    match (service.cpu_percent, service.rss_kb) {
+        (Some(cpu), Some(rss)) => format!(" [CPU {cpu:.1}% | MEM {:.1} MB]", rss as f64 / 1024.0),
+        _ => String::new(),
+    }
+}
+
+fn parse_ps_resources(output: &str) -> HashMap<String, (f32, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.to_string();
+            let cpu = parts.next()?.parse().ok()?;
+            let rss = parts.next()?.parse().ok()?;
+            Some((pid, (cpu, rss)))
+        })
+        .collect()
+}
+
+const NOTIFICATION_DEBOUNCE: Duration = Duration::from_secs(30);
+
+fn send_notification(message: &str) {
+    let script = format!(
+        "display notification \"{}\" with title \"Service Manager\"",
+        escape_quoted(message)
+    );
+    let _ = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .traced_output();
+}
+
+fn restore_terminal_and_exit() -> ! {
+    let term = console::Term::stderr();
+    let _ = term.show_cursor();
+    let _ = term.clear_line();
+    std::process::exit(130);
+}
+
+fn guard_interactive_prompt() {
+    // dialoguer hides the cursor while a prompt is active and leaves it hidden on Ctrl-C.
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            restore_terminal_and_exit();
+        }
+    });
+}
+
+// dialoguer misbehaves when handed nothing to pick from, so check before building a prompt.
+fn ensure_candidates(items: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if items.is_empty() {
+        return Err("No services to choose from".into());
+    }
+    Ok(())
+}
+
+fn prompt_result<T>(result: dialoguer::Result<T>) -> Result<T, Box<dyn std::error::Error>> {
+    match result {
+        Err(dialoguer::Error::IO(err)) if err.kind() == std::io::ErrorKind::Interrupted => {
+            restore_terminal_and_exit()
+        }
+        other => Ok(other?),
+    }
+}
+
+impl Service {
+    fn has_failed(&self) -> bool {
+        self.status == ServiceStatus::Errored || self.last_exit_code.is_some_and(|code| code != 0)
+    }
+
+    // Path-style launchd jobs are shown by file name but must be addressed by their raw label.
+    fn operation_name(&self) -> &str {
+        match self.service_type {
+            ServiceType::Launchd => self.label.as_deref().unwrap_or(&self.name),
+            ServiceType::Brew => &self.name,
+        }
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.label.as_deref() == Some(name)
+    }
+}
+
+type ServiceSink<'a> = dyn FnMut(Service) -> Result<(), Box<dyn std::error::Error>> + 'a;
+
+// `from` is null on the first tick (the baseline) and either side is null while the
+// service is not listed at all.
+#[derive(Debug, Serialize)]
+struct WatchEvent {
+    ts: String,
+    name: String,
+    from: Option<ServiceStatus>,
+    to: Option<ServiceStatus>,
+}
+
+fn emit_watch_event(event: &WatchEvent) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, event)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ServiceGauge {
+    running: usize,
+    stopped: usize,
+    total: usize,
+    running_ratio: f64,
+}
+
+impl ServiceGauge {
+    // Anything listed but not running (stopped, errored, disabled, ...) counts as stopped.
+    fn new(services: &[Service]) -> Self {
+        let running = services
+            .iter()
+            .filter(|s| s.status == ServiceStatus::Running)
+            .count();
+        let total = services.len();
+        ServiceGauge {
+            running,
+            stopped: total - running,
+            total,
+            running_ratio: if total == 0 {
+                0.0
+            } else {
+                running as f64 / total as f64
+            },
+        }
+    }
+
+    fn to_prometheus(&self) -> String {
+        [
+            ("running_total", "Number of listed services that are running", self.running),
+            ("stopped_total", "Number of listed services that are not running", self.stopped),
+        ]
+        .iter()
+        .map(|(metric, help, value)| {
+            format!(
+                "# HELP service_manager_{metric} {help}\n# TYPE service_manager_{metric} gauge\nservice_manager_{metric} {value}\n"
+            )
+        })
+        .collect()
+    }
+}
+
+fn emit_json_line(service: Service) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &service)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct ListCache {
+    created: u64,
+    services: Vec<Service>,
+}
+
+fn status_breakdown(services: &[Service]) -> Option<String> {
+    let mut counts: BTreeMap<ServiceType, BTreeMap<(u8, String), usize>> = BTreeMap::new();
+    for service in services {
+        *counts
+            .entry(service.service_type)
+            .or_default()
+            .entry((service.status.sort_rank(), service.status.to_string()))
+            .or_default() += 1;
+    }
+    if counts.len() < 2 {
+        return None;
+    }
+
+    let parts: Vec<String> = counts
+        .iter()
+        .map(|(service_type, statuses)| {
+            let statuses: Vec<String> = statuses
+                .iter()
+                .map(|((_, status), count)| format!("{count} {status}"))
+                .collect();
+            format!("{service_type}: {}", statuses.join(" / "))
+        })
+        .collect();
+    Some(parts.join(" | "))
+}
+
+struct ListDisplay {
+    pager: bool,
+    resources: bool,
+    total: usize,
+    hidden: usize,
+    hidden_apple: usize,
+    columns: Vec<Column>,
+    no_header: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    #[serde(default)]
+    services: BTreeMap<String, ServiceConfig>,
+    #[serde(default)]
+    defaults: BTreeMap<String, toml::Table>,
+    #[serde(default)]
+    tiers: BTreeMap<String, Tier>,
+    #[serde(skip)]
+    tier_patterns: Vec<(NamePattern, Tier)>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ServiceConfig {
+    alias: Option<String>,
+    note: Option<String>,
+    tier: Option<Tier>,
+}
+
+// Declared most severe first, so the minimum of several matching tiers is the one that counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Tier {
+    Critical,
+    Important,
+    Low,
+}
+
+impl Tier {
+    // Only a service that matters and is down gets the loud treatment; low-tier rows fade out.
+    fn tint(self, name: String, status: &ServiceStatus) -> ColoredString {
+        let down = *status != ServiceStatus::Running;
+        match self {
+            Tier::Critical if down => name.red().bold(),
+            Tier::Important if down => name.yellow().bold(),
+            Tier::Low => name.dimmed(),
+            _ => name.bold(),
+        }
+    }
+}
+
+impl Config {
+    fn default_path() -> PathBuf {
+        match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir).join("service-manager/config.toml"),
+            None => expand_home("~/.config/service-manager/config.toml"),
+        }
+    }
+
+    // A missing default config is fine; a missing explicit --config is a typo worth reporting.
+    fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let explicit = path.is_some();
+        let path = path.map_or_else(Self::default_path, Path::to_path_buf);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display()).into()),
+        };
+        let mut config: Self = toml::from_str(&contents).map_err(|e| {
+            let line = e
+                .span()
+                .map_or(0, |span| contents[..span.start].lines().count().max(1));
+            format!("Invalid config {}:{line}: {}", path.display(), e.message())
+        })?;
+        let command = Cli::command();
+        if let Some(name) = config
+            .defaults
+            .keys()
+            .find(|name| command.find_subcommand(name).is_none())
+        {
+            return Err(
+                format!("Unknown subcommand [defaults.{name}] in {}", path.display()).into(),
+            );
+        }
+        config.tier_patterns = config
+            .tiers
+            .iter()
+            .map(|(pattern, tier)| {
+                parse_name_pattern(pattern)
+                    .map(|pattern| (pattern, *tier))
+                    .map_err(|err| {
+                        format!(
+                            "Invalid tier pattern '{pattern}' in {}: {err}",
+                            path.display()
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let mut seen = HashSet::new();
+        for alias in config.services.values().filter_map(|s| s.alias.as_deref()) {
+            if !seen.insert(alias) {
+                return Err(format!(
+                    "Alias '{alias}' is used for more than one service in {}",
+                    path.display()
+                )
+                .into());
+            }
+        }
+        Ok(config)
+    }
+
+    fn service(&self, name: &str) -> Option<&ServiceConfig> {
+        self.services.get(name)
+    }
+
+    // A tier set on the service itself wins over patterns; among patterns the most severe wins.
+    fn tier(&self, name: &str) -> Option<Tier> {
+        self.service(name).and_then(|s| s.tier).or_else(|| {
+            self.tier_patterns
+                .iter()
+                .filter(|(pattern, _)| pattern.matches(name))
+                .map(|(_, tier)| *tier)
+                .min()
+        })
+    }
+
+    fn resolve_alias(&self, name: &str) -> String {
+        self.services
+            .iter()
+            .find(|(_, service)| service.alias.as_deref() == Some(name))
+            .map_or_else(|| name.to_string(), |(label, _)| label.clone())
+    }
+}
+
+// Tally for a batch of starts/stops. On a terminal it is one line rewritten in place and
+// cleared around any other output; otherwise each result is printed as a plain line.
+struct BatchProgress {
+    action: &'static str,
+    total: usize,
+    live: bool,
+    active: Vec<String>,
+    results: Vec<(String, Option<String>)>,
+}
+
+impl BatchProgress {
+    fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, error)| error.is_some())
+            .count()
+    }
+
+    fn counter(&self) -> String {
+        format!("[{}/{}]", self.results.len(), self.total)
+    }
+
+    fn clear(&self) {
+        if self.live {
+            let _ = console::Term::stdout().clear_line();
+        }
+    }
+
+    fn draw(&self) {
+        if !self.live {
+            return;
+        }
+        let failed = self.failed();
+        let line = format!(
+            "{} {} {} {} {}  {} {}",
+            self.counter(),
+            icon(Icon::Ok),
+            self.results.len() - failed,
+            icon(Icon::Error),
+            failed,
+            self.action,
+            self.active.join(", ")
+        );
+        let width = usize::from(console::Term::stdout().size().1);
+        self.clear();
+        print!(
+            "{}",
+            console::truncate_str(&line, width.saturating_sub(1), "…")
+        );
+        let _ = std::io::stdout().flush();
+    }
+}
+
+struct ServiceManager {
+    brew_path: Option<PathBuf>,
+    uid: Option<u32>,
+    macos: std::cell::OnceCell<Option<MacosVersion>>,
+    dry_run: bool,
+    explain: bool,
+    machine_output: bool,
+    brew_cache: RefCell<Option<Vec<Service>>>,
+    cache_ttl: Option<Duration>,
+    passthrough_args: Vec<String>,
+    verify: bool,
+    concurrency: usize,
+    start_attempts: u32,
+    config: Config,
+    show_alias: bool,
+    is_root: bool,
+    sudo: bool,
+    progress: RefCell<Option<BatchProgress>>,
+}
+
+impl ServiceManager {
+    fn new(dry_run: bool, uid_override: Option<u32>) -> Result<Self, Box<dyn std::error::Error>> {
+        let brew_path = Self::check_brew_availability();
+        let current_uid = Self::current_uid();
+        if let Some(target) = uid_override {
+            if current_uid != Some(target) && current_uid != Some(0) {
+                return Err(format!(
+                    "Managing services for uid {target} requires root, re-run with sudo"
+                )
+                .into());
+            }
+        }
+        let uid = uid_override.or(current_uid);
+        Ok(Self {
+            brew_path,
+            uid,
+            macos: std::cell::OnceCell::new(),
+            dry_run,
+            explain: false,
+            machine_output: false,
+            brew_cache: RefCell::new(None),
+            cache_ttl: None,
+            passthrough_args: Vec::new(),
+            verify: false,
+            concurrency: usize::from(DEFAULT_CONCURRENCY),
+            start_attempts: 1,
+            config: Config::default(),
+            show_alias: false,
+            is_root: current_uid == Some(0),
+            sudo: false,
+            progress: RefCell::new(None),
+        })
+    }
+
+    fn current_uid() -> Option<u32> {
+        let output = Command::new("id").arg("-u").traced_output().ok()?;
+        str::from_utf8(&output.stdout).ok()?.trim().parse().ok()
+    }
+
+    fn display_name(&self, service: &Service) -> String {
+        let alias = self
+            .config
+            .service(&service.name)
+            .and_then(|s| s.alias.as_deref());
+        match alias {
+            Some(alias) if self.show_alias => alias.to_string(),
+            Some(alias) => format!("{} ({alias})", service.name),
+            None => service.name.clone(),
+        }
+    }
+
+    fn tinted_name(&self, service: &Service) -> ColoredString {
+        let name = self.display_name(service);
+        match self.config.tier(&service.name) {
+            Some(tier) => tier.tint(name, &service.status),
+            None => name.bold(),
+        }
+    }
+
+    fn print_note(&self, name: &str) {
+        if let Some(note) = self.config.service(name).and_then(|s| s.note.as_deref()) {
+            println!("{}", format!("{} {note}", icon(Icon::Note)).dimmed());
+        }
+    }
+
+    // Detected on first use so commands that never need it skip the sw_vers call.
+    fn macos(&self) -> Option<MacosVersion> {
+        *self.macos.get_or_init(MacosVersion::detect)
+    }
+
+    // Without sw_vers (e.g. a shim in CI) assume a current macOS.
+    fn supports_bootstrap(&self) -> bool {
+        self.macos()
+            .is_none_or(|version| version.supports_bootstrap())
+    }
+
+    fn supports_print(&self) -> bool {
+        self.macos().is_none_or(|version| version.supports_print())
+    }
+
+    fn bootout_command(&self, domain: &str, label: &str, path: &Path) -> Command {
+        let mut command = Command::new("launchctl");
+        if self.supports_bootstrap() {
+            command.arg("bootout").arg(format!("{domain}/{label}"));
+        } else {
+            command.arg("unload").arg(path);
+        }
+        command
+    }
+
+    // Start/stop for launchd, persisting the choice like `load -w`/`unload -w` do. On macOS
+    // with bootstrap that is `enable` + `bootstrap` and `bootout` + `disable`; a job with no
+    // plist on disk can only be kickstarted. Passthrough args go on the load/unload call.
+    fn launchd_toggle_commands(
+        &self,
+        service_name: &str,
+        start: bool,
+    ) -> Result<Vec<Command>, Box<dyn std::error::Error>> {
+        if !self.supports_bootstrap() {
+            let mut command = Command::new("launchctl");
+            command
+                .arg(if start { "load" } else { "unload" })
+                .arg("-w")
+                .args(&self.passthrough_args)
+                .arg(self.launchd_target(service_name));
+            return Ok(vec![command]);
+        }
+
+        let location = self.resolve_plist(service_name);
+        let domain = self.domain_target(
+            location
+                .as_ref()
+                .map_or(LaunchDomain::Gui, |location| location.domain),
+        )?;
+        let target = format!(
+            "{domain}/{}",
+            location
+                .as_ref()
+                .map_or(service_name, |location| location.label.as_str())
+        );
+        let mut toggle = Command::new("launchctl");
+        toggle
+            .arg(if start { "enable" } else { "disable" })
+            .arg(&target);
+        let mut command = Command::new("launchctl");
+        match (&location, start) {
+            (Some(location), true) => command
+                .arg("bootstrap")
+                .args(&self.passthrough_args)
+                .arg(&domain)
+                .arg(&location.path),
+            (None, true) => command
+                .arg("kickstart")
+                .args(&self.passthrough_args)
+                .arg(&target),
+            (_, false) => command
+                .arg("bootout")
+                .args(&self.passthrough_args)
+                .arg(&target),
+        };
+        Ok(if start {
+            vec![toggle, command]
+        } else {
+            vec![command, toggle]
+        })
+    }
+
+    fn bootstrap_command(&self, domain: &str, path: &Path) -> Command {
+        let mut command = Command::new("launchctl");
+        if self.supports_bootstrap() {
+            command.arg("bootstrap").arg(domain).arg(path);
+        } else {
+            command.arg("load").arg(path);
+        }
+        command
+    }
+
+    fn domain_target(&self, domain: LaunchDomain) -> Result<String, Box<dyn std::error::Error>> {
+        match domain {
+            LaunchDomain::System => Ok("system".to_string()),
+            LaunchDomain::Gui => self
+                .uid
+                .map(|uid| format!("gui/{uid}"))
+                .ok_or_else(|| "Could not determine the current user id".into()),
+        }
+    }
+
+    fn resolve_plist(&self, service: &str) -> Option<PlistLocation> {
+        let given = Path::new(service);
+        if given.extension().is_some_and(|ext| ext == "plist") && given.is_file() {
+            // launchd registers the job under its Label key, which need not match the filename.
+            let label = read_plist(given)
+                .and_then(|plist| plist.get("Label")?.as_string().map(str::to_string))
+                .unwrap_or(given.file_stem()?.to_string_lossy().to_string());
+            return Some(PlistLocation {
+                label,
+                path: given.to_path_buf(),
+                domain: domain_for_path(given),
+            });
+        }
+
+        PLIST_SEARCH_DIRS.iter().find_map(|(dir, domain)| {
+            let path = expand_home(dir).join(format!("{service}.plist"));
+            path.is_file().then(|| PlistLocation {
+                label: service.to_string(),
+                path,
+                domain: *domain,
+            })
+        })
+    }
+
+    fn check_brew_availability() -> Option<PathBuf> {
+        let from_path = Command::new("which")
+            .arg("brew")
+            .traced_output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                let path = str::from_utf8(&output.stdout).ok()?.trim().to_string();
+                (!path.is_empty()).then(|| PathBuf::from(path))
+            });
+
+        // Non-login shells often lack brew on PATH, so probe the standard prefixes too.
+        from_path.or_else(|| {
+            BREW_PREFIX_PATHS
+                .iter()
+                .map(PathBuf::from)
+                .find(|path| path.is_file())
+        })
+    }
+
+    fn brew_available(&self) -> bool {
+        self.brew_path.is_some()
+    }
+
+    fn brew_command(&self) -> Command {
+        let mut command = Command::new(self.brew_path.as_deref().unwrap_or(Path::new("brew")));
+        // Listing and start/stop never need brew's auto-update or analytics round-trips.
+        command
+            .env("HOMEBREW_NO_AUTO_UPDATE", "1")
+            .env("HOMEBREW_NO_ANALYTICS", "1");
+        command
+    }
+
+    async fn list_launchd_services(
+        &self,
+        query: &ServiceQuery,
+    ) -> Result<Vec<Service>, Box<dyn std::error::Error>> {
+        let mut services = Vec::new();
+        self.stream_launchd_services(query, &mut |service| {
+            services.push(service);
+            Ok(())
+        })
+        .await?;
+        Ok(services)
+    }
+
+    async fn stream_launchd_services(
+        &self,
+        query: &ServiceQuery,
+        emit: &mut ServiceSink<'_>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.cache_ttl.is_none() {
+            return self.fetch_launchd_services(query, emit);
+        }
+
+        let services = match self.read_list_cache(ServiceType::Launchd) {
+            Some(services) => services,
+            None => {
+                let mut services = Vec::new();
+                self.fetch_launchd_services(&ServiceQuery::all(), &mut |service| {
+                    services.push(service);
+                    Ok(())
+                })?;
+                self.write_list_cache(ServiceType::Launchd, &services);
+                services
+            }
+        };
+        for service in services {
+            if query.matches(&service) {
+                emit(service)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fetch_launchd_services(
+        &self,
+        query: &ServiceQuery,
+        emit: &mut ServiceSink<'_>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let output = Command::new("launchctl").arg("list").traced_output()?;
+        // An error with empty stdout would otherwise be indistinguishable from "no services".
+        if !output.status.success() && output.stdout.is_empty() {
+            return Err(ServiceError::command_failed(
+                "launchctl list failed",
+                &String::from_utf8_lossy(&output.stderr),
+            )
+            .into());
+        }
+
+        let output_str = str::from_utf8(&output.stdout)?;
+        let mut loaded = HashSet::new();
+
+        for entry in parse_launchctl_list(output_str) {
+            let status = ServiceStatus::from_launchd(entry.pid.as_deref());
+            loaded.insert(entry.label.clone());
+
+            let (name, label) = match clean_launchd_label(&entry.label) {
+                Some(name) => (name, Some(entry.label)),
+                None => (entry.label, None),
+            };
+            let service = Service {
+                name,
+                status,
+                pid: entry.pid,
+                service_type: ServiceType::Launchd,
+                last_exit_code: entry.last_exit_code,
+                cpu_percent: None,
+                rss_kb: None,
+                label,
+                plist_path: None,
+                user: None,
+            };
+            if query.matches(&service) {
+                emit(service)?;
+            }
+        }
+
+        if !query.running_only {
+            // Disabled services aren't loaded, so `launchctl list` never reports them.
+            for name in self.disabled_labels() {
+                let service = Service {
+                    name: name.clone(),
+                    status: ServiceStatus::Disabled,
+                    pid: None,
+                    service_type: ServiceType::Launchd,
+                    last_exit_code: None,
+                    cpu_percent: None,
+                    rss_kb: None,
+                    label: None,
+                    plist_path: None,
+                    user: None,
+                };
+                if loaded.insert(name) && query.matches(&service) {
+                    emit(service)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn list_cache_path(&self, service_type: ServiceType) -> PathBuf {
+        let uid = self
+            .uid
+            .map_or("unknown".to_string(), |uid| uid.to_string());
+        std::env::temp_dir().join(format!("service-manager-{uid}-{service_type}.json"))
+    }
+
+    fn read_list_cache(&self, service_type: ServiceType) -> Option<Vec<Service>> {
+        let ttl = self.cache_ttl?;
+        let contents = std::fs::read(self.list_cache_path(service_type)).ok()?;
+        let cache: ListCache = serde_json::from_slice(&contents).ok()?;
+        let age = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .checked_sub(Duration::from_secs(cache.created))?;
+        (age < ttl).then_some(cache.services)
+    }
+
+    fn write_list_cache(&self, service_type: ServiceType, services: &[Service]) {
+        if self.cache_ttl.is_none() {
+            return;
+        }
+        let Ok(now) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) else {
+            return;
+        };
+        let cache = ListCache {
+            created: now.as_secs(),
+            services: services.to_vec(),
+        };
+        if let Ok(json) = serde_json::to_vec(&cache) {
+            let _ = std::fs::write(self.list_cache_path(service_type), json);
+        }
+    }
+
+    fn clear_list_cache(&self) {
+        for service_type in [ServiceType::Launchd, ServiceType::Brew] {
+            let _ = std::fs::remove_file(self.list_cache_path(service_type));
+        }
+    }
+
+    fn disabled_labels(&self) -> Vec<String> {
+        [LaunchDomain::Gui, LaunchDomain::System]
+            .into_iter()
+            .filter_map(|domain| self.domain_target(domain).ok())
+            .filter_map(|target| {
+                Command::new("launchctl")
+                    .arg("print-disabled")
+                    .arg(target)
+                    .traced_output()
+                    .ok()
+            })
+            .filter(|output| output.status.success())
+            .flat_map(|output| parse_print_disabled(&String::from_utf8_lossy(&output.stdout)))
+            .collect()
+    }
+
+    async fn list_brew_services(
+        &self,
+        query: &ServiceQuery,
+    ) -> Result<Vec<Service>, Box<dyn std::error::Error>> {
+        let mut services = Vec::new();
+        self.stream_brew_services(query, &mut |service| {
+            services.push(service);
+            Ok(())
+        })
+        .await?;
+        Ok(services)
+    }
+
+    async fn stream_brew_services(
+        &self,
+        query: &ServiceQuery,
+        emit: &mut ServiceSink<'_>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.brew_available() {
+            return Ok(());
+        }
+
+        if self.brew_cache.borrow().is_none() {
+            let services = match self.read_list_cache(ServiceType::Brew) {
+                Some(services) => services,
+                None => {
+                    let services = self.fetch_brew_services()?;
+                    self.write_list_cache(ServiceType::Brew, &services);
+                    services
+                }
+            };
+            *self.brew_cache.borrow_mut() = Some(services);
+        }
+
+        let cache = self.brew_cache.borrow();
+        for service in cache.iter().flatten() {
+            if query.matches(service) {
+                emit(service.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fetch_brew_services(&self) -> Result<Vec<Service>, Box<dyn std::error::Error>> {
+        let output = self
+            .brew_command()
+            .args(["services", "list", "--json"])
+            .traced_output()?;
+
+        let entries: Vec<BrewServiceEntry> = serde_json::from_slice(&output.stdout)?;
+        let services = entries
+            .into_iter()
+            .map(|entry| {
+                // Tapped formulae don't always follow homebrew.mxcl.<name>, so trust the plist.
+                let plist_path = entry.file.as_deref().map(expand_home);
+                let label = plist_path
+                    .as_deref()
+                    .and_then(read_plist)
+                    .and_then(|plist| plist.get("Label")?.as_string().map(str::to_string))
+                    .unwrap_or_else(|| brew_label(&entry.name));
+                Service {
+                    name: entry.name,
+                    status: ServiceStatus::from_brew(&entry.status),
+                    pid: None,
+                    service_type: ServiceType::Brew,
+                    last_exit_code: entry.exit_code,
+                    cpu_percent: None,
+                    rss_kb: None,
+                    label: Some(label),
+                    plist_path,
+                    user: entry.user,
+                }
+            })
+            .collect();
+
+        Ok(services)
+    }
+
+    fn invalidate_brew_cache(&self) {
+        self.brew_cache.borrow_mut().take();
+    }
+
+    // Without sudo, brew would manage a second copy in the user's own domain and leave the
+    // root-owned daemon untouched, which looks like a silent no-op. Returns whether the brew
+    // call has to be elevated.
+    async fn brew_needs_root(
+        &self,
+        service_name: &str,
+        action: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.is_root || !self.brew_runs_as_root(service_name).await? {
+            return Ok(false);
+        }
+        if !self.sudo {
+            return Err(format!(
+                "Brew service '{service_name}' runs as root; re-run with --sudo or use: sudo brew services {action} {service_name}"
+            )
+            .into());
+        }
+        Ok(true)
+    }
+
+    async fn brew_runs_as_root(
+        &self,
+        service_name: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let user = self
+            .list_brew_services(&ServiceQuery::all())
+            .await?
+            .into_iter()
+            .find(|s| s.name == service_name)
+            .and_then(|s| s.user);
+        Ok(user.as_deref() == Some("root"))
+    }
+
+    // Concurrent sudo password prompts would fight over the terminal, so a batch that has to
+    // elevate any of its commands acts on one service at a time.
+    async fn batch_limit<'a>(&self, services: impl IntoIterator<Item = (&'a str, bool)>) -> usize {
+        if !self.sudo || self.is_root {
+            return self.concurrency;
+        }
+        for (name, is_brew) in services {
+            let elevated = if is_brew {
+                self.brew_runs_as_root(name).await.unwrap_or(true)
+            } else {
+                self.is_system_service(name)
+            };
+            if elevated {
+                return 1;
+            }
+        }
+        self.concurrency
+    }
+
+    fn is_system_service(&self, service_name: &str) -> bool {
+        self.resolve_plist(service_name)
+            .is_some_and(|location| location.domain == LaunchDomain::System)
+    }
+
+    // Only the one privileged subprocess goes through sudo; the tool itself keeps running as
+    // the user, so caches, config and the brew user checks stay per-user.
+    fn elevate(&self, command: Command, privileged: bool) -> Command {
+        if privileged && self.sudo && !self.is_root {
+            with_sudo(&command)
+        } else {
+            command
+        }
+    }
+
+    async fn start_service(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            let privileged = self.brew_needs_root(service_name, "start").await?;
+            let mut command = self.brew_command();
+            command
+                .arg("services")
+                .arg("start")
+                .arg(service_name)
+                .args(&self.passthrough_args);
+            let mut command = self.elevate(command, privileged);
+            let explained = self.skip_for_explain(
+                || {
+                    Ok(format!(
+                        "This will have Homebrew write the LaunchAgent {} for the {service_name} formula and start it now; it will also start at every login until stopped",
+                        brew_label(service_name)
+                    ))
+                },
+                &[&command],
+            )?;
+            if explained || self.skip_for_dry_run(&command) {
+                return Ok(());
+            }
+            let output = command.traced_output_async().await?;
+            self.invalidate_brew_cache();
+
+            if output.status.success() {
+                self.verify_outcome(service_name, is_brew, true).await?;
+                self.report_brew_output(
+                    format!("{} Brew service '{service_name}' started", icon(Icon::Ok)).green(),
+                    &output.stdout,
+                );
+            } else {
+                let error = str::from_utf8(&output.stderr)?;
+                return Err(ServiceError::command_failed("Failed to start service", error).into());
+            }
+        } else {
+            let privileged = self.is_system_service(service_name);
+            let mut commands: Vec<Command> = self
+                .launchd_toggle_commands(service_name, true)?
+                .into_iter()
+                .map(|command| self.elevate(command, privileged))
+                .collect();
+            self.warn_label_mismatch(service_name);
+            let explained = self.skip_for_explain(
+                || {
+                    let (subject, domain) = self.launchd_subject(service_name)?;
+                    Ok(format!(
+                        "This will load {subject} into the {domain} domain and clear its disabled override, so launchd starts it now and loads it again in future sessions"
+                    ))
+                },
+                &commands.iter().collect::<Vec<_>>(),
+            )?;
+            if explained || self.skip_all_for_dry_run(&commands) {
+                return Ok(());
+            }
+            for command in &mut commands {
+                let output = command.traced_output_async().await?;
+                if !output.status.success() {
+                    let error = str::from_utf8(&output.stderr)?.trim();
+                    let message = match self.session_mismatch(service_name) {
+                        Some(mismatch) => format!("Failed to start service ({mismatch})"),
+                        None => "Failed to start service".to_string(),
+                    };
+                    return Err(ServiceError::command_failed(message, error).into());
+                }
+            }
+            self.verify_outcome(service_name, is_brew, true).await?;
+            self.report(
+                format!(
+                    "{} Launchd service '{service_name}' started",
+                    icon(Icon::Ok)
+                )
+                .green(),
+            );
+        }
+        Ok(())
+    }
+
+    async fn verify_outcome(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        expect_running: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.verify {
+            return Ok(());
+        }
+
+        // launchctl can report the old state for a moment, so give it a few polls to settle.
+        for attempt in 0..VERIFY_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(400)).await;
+            }
+            let services = if is_brew {
+                self.invalidate_brew_cache();
+                self.list_brew_services(&ServiceQuery::running()).await?
+            } else {
+                self.list_launchd_services(&ServiceQuery::running()).await?
+            };
+            if services.iter().any(|s| s.is_named(service_name)) == expect_running {
+                return Ok(());
+            }
+        }
+
+        let observed = if expect_running {
+            "is not running after start"
+        } else {
+            "is still running after stop"
+        };
+        Err(format!("Verification failed: '{service_name}' {observed}").into())
+    }
+
+    async fn stop_service(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            let privileged = self.brew_needs_root(service_name, "stop").await?;
+            let mut command = self.brew_command();
+            command
+                .arg("services")
+                .arg("stop")
+                .arg(service_name)
+                .args(&self.passthrough_args);
+            let mut command = self.elevate(command, privileged);
+            let explained = self.skip_for_explain(
+                || {
+                    Ok(format!(
+                        "This will have Homebrew stop the {service_name} formula and remove its LaunchAgent {}, so it no longer starts at login",
+                        brew_label(service_name)
+                    ))
+                },
+                &[&command],
+            )?;
+            if explained || self.skip_for_dry_run(&command) {
+                return Ok(());
+            }
+            let output = command.traced_output_async().await?;
+            self.invalidate_brew_cache();
+
+            if output.status.success() {
+                self.verify_outcome(service_name, is_brew, false).await?;
+                self.report_brew_output(
+                    format!("{} Brew service '{service_name}' stopped", icon(Icon::Stop)).red(),
+                    &output.stdout,
+                );
+            } else {
+                let error = str::from_utf8(&output.stderr)?;
+                return Err(ServiceError::command_failed("Failed to stop service", error).into());
+            }
+        } else {
+            let privileged = self.is_system_service(service_name);
+            let mut commands: Vec<Command> = self
+                .launchd_toggle_commands(service_name, false)?
+                .into_iter()
+                .map(|command| self.elevate(command, privileged))
+                .collect();
+            let explained = self.skip_for_explain(
+                || {
+                    let (subject, domain) = self.launchd_subject(service_name)?;
+                    Ok(format!(
+                        "This will unload {subject} from the {domain} domain, stopping it, and set a disabled override so it stays unloaded in future sessions"
+                    ))
+                },
+                &commands.iter().collect::<Vec<_>>(),
+            )?;
+            if explained || self.skip_all_for_dry_run(&commands) {
+                return Ok(());
+            }
+            for command in &mut commands {
+                let output = command.traced_output_async().await?;
+                if !output.status.success() {
+                    let error = str::from_utf8(&output.stderr)?;
+                    return Err(
+                        ServiceError::command_failed("Failed to stop service", error).into(),
+                    );
+                }
+            }
+            self.verify_outcome(service_name, is_brew, false).await?;
+            self.report(
+                format!(
+                    "{} Launchd service '{service_name}' stopped",
+                    icon(Icon::Stop)
+                )
+                .red(),
+            );
+        }
+        Ok(())
+    }
+
+    async fn restart_service(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            let privileged = self.brew_needs_root(service_name, "restart").await?;
+            let mut command = self.brew_command();
+            command.args(["services", "restart", service_name]);
+            let mut command = self.elevate(command, privileged);
+            let explained = self.skip_for_explain(
+                || {
+                    Ok(format!(
+                        "This will have Homebrew stop and start the {service_name} formula through its LaunchAgent {}",
+                        brew_label(service_name)
+                    ))
+                },
+                &[&command],
+            )?;
+            if explained || self.skip_for_dry_run(&command) {
+                return Ok(());
+            }
+            let output = command.traced_output_async().await?;
+            self.invalidate_brew_cache();
+            if !output.status.success() {
+                let error = str::from_utf8(&output.stderr)?;
+                return Err(
+                    ServiceError::command_failed("Failed to restart service", error).into(),
+                );
+            }
+            self.report_brew_output(
+                format!(
+                    "{} Brew service '{service_name}' restarted",
+                    icon(Icon::Reload)
+                )
+                .green(),
+                &output.stdout,
+            );
+            return Ok(());
+        }
+
+        // kickstart arrived with the bootstrap-era launchctl; older systems only have unload/load.
+        if !self.supports_bootstrap() {
+            return self.reload_service(service_name).await;
+        }
+
+        let (label, domain) = match self.resolve_plist(service_name) {
+            Some(location) => (location.label, location.domain),
+            None => (service_name.to_string(), LaunchDomain::Gui),
+        };
+        let privileged = domain == LaunchDomain::System;
+        let domain = self.domain_target(domain)?;
+        let mut command = Command::new("launchctl");
+        command
+            .arg("kickstart")
+            .arg("-k")
+            .arg(format!("{domain}/{label}"));
+        let mut command = self.elevate(command, privileged);
+        let explained = self.skip_for_explain(
+            || {
+                Ok(format!(
+                    "This will kill the running {label} in the {domain} domain and have launchd start it again right away, without re-reading its plist"
+                ))
+            },
+            &[&command],
+        )?;
+        if explained || self.skip_for_dry_run(&command) {
+            return Ok(());
+        }
+        let output = command.traced_output_async().await?;
+        if !output.status.success() {
+            let error = str::from_utf8(&output.stderr)?;
+            return Err(ServiceError::command_failed(
+                format!("Failed to restart service (is '{label}' loaded? try start)"),
+                error,
+            )
+            .into());
+        }
+
+        let pid = self
+            .list_launchd_services(&ServiceQuery::running())
+            .await?
+            .into_iter()
+            .find(|s| s.is_named(&label))
+            .and_then(|s| s.pid);
+        match pid {
+            Some(pid) => self.report(
+                format!(
+                    "{} Launchd service '{label}' restarted (PID: {pid})",
+                    icon(Icon::Reload)
+                )
+                .green(),
+            ),
+            None => self.report(
+                format!(
+                    "{} Launchd service '{label}' was kickstarted but has no PID yet",
+                    icon(Icon::Warning)
+                )
+                .yellow(),
+            ),
+        }
+        Ok(())
+    }
+
+    async fn get_service_status(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        first_match: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            let services = self.list_brew_services(&ServiceQuery::all()).await?;
+            let by_label = services.iter().find(|s| {
+                s.label.as_deref() == Some(service_name) || brew_label(&s.name) == service_name
+            });
+            let found = match by_label {
+                Some(service) => Some(service),
+                None => find_service(&services, service_name, first_match)?,
+            };
+            if let Some(service) = found {
+                println!(
+                    "{} Brew Service: {} - Status: {}",
+                    icon(Icon::Info),
+                    self.display_name(service).blue(),
+                    service.status.colored()
+                );
+                self.print_note(&service.name);
+            } else {
+                println!(
+                    "{}",
+                    not_found_message("Brew", &services, service_name).red()
+                );
+            }
+        } else {
+            let services = self.list_launchd_services(&ServiceQuery::all()).await?;
+            if let Some(service) = find_service(&services, service_name, first_match)? {
+                let pid_info = service
+                    .pid
+                    .as_ref()
+                    .map_or("N/A".to_string(), |p| p.clone());
+                println!(
+                    "{} Launchd Service: {} - Status: {} - PID: {}",
+                    icon(Icon::Info),
+                    self.display_name(service).blue(),
+                    service.status.colored(),
+                    pid_info.cyan()
+                );
+                self.print_note(&service.name);
+            } else {
+                println!(
+                    "{}",
+                    not_found_message("Launchd", &services, service_name).red()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // One list query and an exact match only, so it stays cheap enough to run on every prompt.
+    async fn oneline_status(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+    ) -> Result<ServiceStatus, Box<dyn std::error::Error>> {
+        let services = if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            self.list_brew_services(&ServiceQuery::all()).await?
+        } else {
+            self.list_launchd_services(&ServiceQuery::running()).await?
+        };
+        Ok(services
+            .into_iter()
+            .find(|s| s.is_named(service_name) || (is_brew && brew_label(&s.name) == service_name))
+            .map_or(ServiceStatus::Stopped, |s| s.status))
+    }
+
+    fn brew_tap(&self, formula: &str) -> Option<String> {
+        let output = self
+            .brew_command()
+            .args(["info", "--json=v2", formula])
+            .traced_output()
+            .ok()?;
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        info["formulae"]
+            .get(0)
+            .or_else(|| info["casks"].get(0))?
+            .get("tap")?
+            .as_str()
+            .map(str::to_string)
+    }
+
+    async fn watch_service_status(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        interval: Duration,
+        notify: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if is_brew && !self.brew_available() {
+            return Err(ServiceError::BrewUnavailable.into());
+        }
+
+        let once = interval.is_zero();
+        if !once && !self.machine_output {
+            println!(
+                "{}",
+                format!(
+                    "{} Watching '{service_name}' every {}s (Ctrl-C to stop)",
+                    icon(Icon::Watch),
+                    interval.as_secs()
+                )
+                .blue()
+            );
+        }
+
+        let mut ticker = tokio::time::interval(interval.max(MIN_WATCH_INTERVAL));
+        let mut last_seen: Option<Option<(ServiceStatus, Option<String>)>> = None;
+        let mut restarts = 0;
+        let mut last_notified: Option<tokio::time::Instant> = None;
+
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                _ = ticker.tick() => {}
+            }
+
+            let services = if is_brew {
+                self.invalidate_brew_cache();
+                self.list_brew_services(&ServiceQuery::all()).await?
+            } else {
+                self.list_launchd_services(&ServiceQuery::all()).await?
+            };
+            let current = services
+                .into_iter()
+                .find(|s| s.is_named(service_name))
+                .map(|s| (s.status, s.pid));
+
+            if last_seen.as_ref() == Some(&current) {
+                continue;
+            }
+
+            let previous_pid = last_seen.as_ref().and_then(|seen| seen.as_ref()?.1.clone());
+            let current_pid = current.as_ref().and_then(|(_, pid)| pid.clone());
+            if last_seen.is_some() && current_pid.is_some() && current_pid != previous_pid {
+                restarts += 1;
+            }
+
+            if self.machine_output {
+                // Only state changes are events; a PID change alone is counted as a restart.
+                let from = last_seen
+                    .as_ref()
+                    .and_then(|seen| seen.as_ref().map(|(status, _)| status.clone()));
+                let to = current.as_ref().map(|(status, _)| status.clone());
+                if last_seen.is_none() || from != to {
+                    emit_watch_event(&WatchEvent {
+                        ts: chrono::Local::now()
+                            .to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                        name: service_name.to_string(),
+                        from,
+                        to,
+                    })?;
+                }
+            } else {
+                let timestamp = chrono::Local::now().format("%H:%M:%S");
+                let state = match &current {
+                    Some((status, pid)) => {
+                        let pid_info = pid
+                            .as_ref()
+                            .map_or("".to_string(), |p| format!(" (PID: {p})"));
+                        format!("{}{}", status.colored(), pid_info.cyan())
+                    }
+                    None => "not found".yellow().to_string(),
+                };
+                println!(
+                    "[{}] {} - {}",
+                    timestamp.to_string().dimmed(),
+                    service_name.blue(),
+                    state
+                );
+            }
+
+            let went_down = matches!(
+                current,
+                Some((ServiceStatus::Stopped | ServiceStatus::Errored, _))
+            );
+            // Debounce so a flapping service doesn't flood the notification center.
+            let recently_notified =
+                last_notified.is_some_and(|at| at.elapsed() < NOTIFICATION_DEBOUNCE);
+            if notify && went_down && last_seen.is_some() && !recently_notified {
+                let state = current
+                    .as_ref()
+                    .map_or("gone".to_string(), |(s, _)| s.to_string());
+                send_notification(&format!("{service_name} is now {state}"));
+                last_notified = Some(tokio::time::Instant::now());
+            }
+
+            if once {
+                return Ok(());
+            }
+            last_seen = Some(current);
+        }
+
+        self.report(
+            format!(
+                "{} Observed {restarts} restart(s) of '{service_name}'",
+                icon(Icon::Summary)
+            )
+            .bold(),
+        );
+        Ok(())
+    }
+
+    fn launchctl_blame(&self, domain: LaunchDomain, label: &str) -> Option<String> {
+        if !self.supports_print() {
+            return None;
+        }
+        let target = format!("{}/{label}", self.domain_target(domain).ok()?);
+        let output = Command::new("launchctl")
+            .arg("blame")
+            .arg(target)
+            .traced_output()
+            .ok()?;
+        let reason = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !reason.is_empty()).then_some(reason)
+    }
+
+    fn launchctl_print(&self, domain: LaunchDomain, label: &str) -> Option<String> {
+        if !self.supports_print() {
+            return None;
+        }
+        let target = format!("{}/{label}", self.domain_target(domain).ok()?);
+        let output = Command::new("launchctl")
+            .arg("print")
+            .arg(target)
+            .traced_output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    async fn show_top(
+        &self,
+        sort: SortKey,
+        interval: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let term = console::Term::stdout();
+        let once = interval.is_zero();
+        let mut ticker = tokio::time::interval(interval.max(MIN_WATCH_INTERVAL));
+
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                _ = ticker.tick() => {}
+            }
+
+            let mut services = self.list_launchd_services(&ServiceQuery::running()).await?;
+            self.attach_resources(&mut services);
+            services.retain(|s| s.cpu_percent.is_some());
+            sort_services(&mut services, sort);
+
+            let (rows, _) = term.size();
+            let visible = (rows as usize).saturating_sub(4).max(1);
+
+            term.clear_screen()?;
+            println!(
+                "{}",
+                format!(
+                    "{} Top services by {} — {} ({} with a PID, Ctrl-C to quit)",
+                    icon(Icon::Top),
+                    format!("{sort:?}").to_lowercase(),
+                    chrono::Local::now().format("%H:%M:%S"),
+                    services.len()
+                )
+                .bold()
+                .blue()
+            );
+            println!(
+                "{}",
+                format!("{:>8} {:>7} {:>10}  {}", "PID", "CPU%", "MEM", "NAME").bold()
+            );
+            for service in services.iter().take(visible) {
+                println!(
+                    "{:>8} {:>7.1} {:>10}  {}",
+                    service.pid.as_deref().unwrap_or("-").cyan(),
+                    service.cpu_percent.unwrap_or(0.0),
+                    format!("{:.1} MB", service.rss_kb.unwrap_or(0) as f64 / 1024.0),
+                    service.name
+                );
+            }
+            if once {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn show_info(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        show_secrets: bool,
+        key: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let services = if is_brew {
+            if !self.brew_available() {
+                return Err(ServiceError::BrewUnavailable.into());
+            }
+            self.list_brew_services(&ServiceQuery::all()).await?
+        } else {
+            self.list_launchd_services(&ServiceQuery::all()).await?
+        };
+        let service = services
+            .iter()
+            .find(|s| s.is_named(service_name))
+            .ok_or_else(|| ServiceError::ServiceNotFound {
+                name: service_name.to_string(),
+            })?;
+
+        let label = match &service.label {
+            Some(label) => label.clone(),
+            None if is_brew => brew_label(&service.name),
+            None => service.name.clone(),
+        };
+        let location = self.resolve_plist(&label).or_else(|| {
+            let path = service.plist_path.clone()?;
+            Some(PlistLocation {
+                label: label.clone(),
+                domain: domain_for_path(&path),
+                path,
+            })
+        });
+        if let Some(key) = key {
+            // The service was found in the live list, so a launchd job without a plist is transient.
+            let location = location.ok_or_else(|| {
+                let name = label.clone();
+                if is_brew {
+                    ServiceError::PlistNotFound { name }
+                } else {
+                    ServiceError::TransientService { name }
+                }
+            })?;
+            let plist = read_plist(&location.path)
+                .ok_or_else(|| format!("Failed to parse {}", location.path.display()))?;
+            let value = plist_key_path(&plist, key)?;
+            println!("{}", format_plist_value(value)?);
+            return Ok(());
+        }
+
+        let domain = location
+            .as_ref()
+            .map_or(LaunchDomain::Gui, |location| location.domain);
+        let printed = self.launchctl_print(domain, &label);
+        let blame = service
+            .pid
+            .as_ref()
+            .and_then(|_| self.launchctl_blame(domain, &label));
+        let plist = location
+            .as_ref()
+            .and_then(|location| read_plist(&location.path));
+
+        // The running job's environment is authoritative; fall back to the plist's declaration.
+        let mut environment = printed
+            .as_deref()
+            .map(|output| parse_print_section(output, "environment"))
+            .unwrap_or_default();
+        if environment.is_empty() {
+            if let Some(env) = plist
+                .as_ref()
+                .and_then(|plist| plist.get("EnvironmentVariables")?.as_dictionary())
+            {
+                environment = env
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_string()?.to_string())))
+                    .collect();
+            }
+        }
+
+        if self.machine_output {
+            let mut report = InfoReport::new(
+                service,
+                &label,
+                location.as_ref(),
+                printed.as_deref(),
+                plist,
+                environment,
+                show_secrets,
+            );
+            report.domain = self.domain_target(domain).ok();
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        println!(
+            "{} Service: {}",
+            icon(Icon::Info),
+            service.name.blue().bold()
+        );
+        println!("  Type:   {}", service.service_type);
+        println!("  Status: {}", service.status.colored());
+        println!(
+            "  PID:    {}",
+            service.pid.as_deref().unwrap_or("N/A").cyan()
+        );
+        if let Some(code) = service.last_exit_code {
+            println!("  Exit:   {code}");
+        }
+        if is_brew {
+            println!("  Label:  {label}");
+            if let Some(user) = &service.user {
+                println!("  Owner:  {user}");
+            }
+            if let Some(tap) = self.brew_tap(&service.name) {
+                println!("  Source: {tap}");
+            }
+        }
+        match &location {
+            Some(location) => println!("  Plist:  {}", location.path.display()),
+            None if is_brew => println!("  Plist:  {}", "not found".dimmed()),
+            None => println!(
+                "  Plist:  {}",
+                "none on disk (transient/registered service)".dimmed()
+            ),
+        }
+        if let Ok(target) = self.domain_target(domain) {
+            println!("  Domain: {target}");
+        }
+        let after_boot = service
+            .pid
+            .as_deref()
+            .and_then(|pid| self.process_elapsed(pid))
+            .zip(self.boot_time())
+            .and_then(|(uptime, boot)| seconds_since_boot_at_start(boot, uptime.as_secs()));
+        if let Some(after_boot) = after_boot {
+            let origin = if after_boot <= BOOT_WINDOW_SECS {
+                "boot"
+            } else {
+                "on-demand"
+            };
+            println!(
+                "  Start:  {origin} ({} after boot)",
+                format_uptime(after_boot)
+            );
+        }
+        if let Some(reason) = &blame {
+            println!("  Why:    {} [{}]", explain_blame(reason), reason.dimmed());
+        }
+        let sessions = plist.as_ref().map(session_types).unwrap_or_default();
+        if !sessions.is_empty() {
+            println!("  Session: {}", sessions.join(", "));
+            if let Some(mismatch) = self.session_mismatch(&label) {
+                println!(
+                    "  {}",
+                    format!("{} {mismatch}, so it will not load", icon(Icon::Warning)).yellow()
+                );
+            }
+        }
+
+        let plist_string = |key: &str| {
+            plist
+                .as_ref()
+                .and_then(|plist| plist.get(key)?.as_string())
+                .map(str::to_string)
+        };
+        let unset = || "(unset)".dimmed().to_string();
+        println!(
+            "  WorkDir: {}",
+            plist_string("WorkingDirectory").unwrap_or_else(unset)
+        );
+        let declared_user = plist_string("UserName");
+        let running_user = service
+            .pid
+            .as_deref()
+            .and_then(|pid| self.process_user(pid));
+        println!("  User:   {}", declared_user.clone().unwrap_or_else(unset));
+        if let Some(running_user) = &running_user {
+            match &declared_user {
+                Some(declared) if declared != running_user => println!(
+                    "  {}",
+                    format!(
+                        "{} Running as {running_user}, but the plist declares UserName {declared}",
+                        icon(Icon::Warning)
+                    )
+                    .yellow()
+                ),
+                _ => println!("  Runs as: {running_user}"),
+            }
+        }
+        println!(
+            "  Group:  {}",
+            plist_string("GroupName").unwrap_or_else(unset)
+        );
+
+        println!("{}", format!("{} Environment:", icon(Icon::Env)).bold());
+        if environment.is_empty() {
+            println!("  {}", "(none)".dimmed());
+        }
+        for (key, value) in environment {
+            let value = if !show_secrets && is_secret_key(&key) {
+                "********".dimmed().to_string()
+            } else {
+                value
+            };
+            println!("  {}={}", key.cyan(), value);
+        }
+        Ok(())
+    }
+
+    async fn show_logs(
+        &self,
+        service_name: &str,
+        since: &LogWindow,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut command = Command::new("log");
+        command.arg("show").arg("--style").arg("compact");
+        match since {
+            LogWindow::Last(duration) => {
+                // `log show --last` only understands minutes, hours and days.
+                let minutes = duration.as_secs().div_ceil(60).max(1);
+                command.arg("--last").arg(format!("{minutes}m"));
+            }
+            LogWindow::Start(timestamp) => {
+                command.arg("--start").arg(timestamp);
+            }
+        }
+        command.arg("--predicate").arg(log_predicate(service_name));
+
+        let status = command.traced_status()?;
+        if !status.success() {
+            return Err(format!("Failed to show logs for '{service_name}'").into());
+        }
+        Ok(())
+    }
+
+    async fn follow_logs(
+        &self,
+        service_name: &str,
+        for_duration: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut command = tokio::process::Command::new("log");
+        command
+            .arg("stream")
+            .arg("--style")
+            .arg("compact")
+            .arg("--predicate")
+            .arg(log_predicate(service_name))
+            .kill_on_drop(true);
+        debug!(command = %describe_command(command.as_std()), "spawning log stream");
+        let mut child = command.spawn()?;
+
+        let status = match for_duration {
+            Some(limit) => match tokio::time::timeout(limit, child.wait()).await {
+                Ok(status) => status?,
+                Err(_) => {
+                    child.kill().await?;
+                    eprintln!(
+                        "{}",
+                        format!("{} Stopped after {}s", icon(Icon::Time), limit.as_secs()).dimmed()
+                    );
+                    return Ok(());
+                }
+            },
+            None => child.wait().await?,
+        };
+        if !status.success() {
+            return Err(format!("Failed to stream logs for '{service_name}'").into());
+        }
+        Ok(())
+    }
+
+    // Lines are printed as they arrive; the files carry no shared timestamps to merge on,
+    // so arrival order is the closest thing to timestamp order.
+    async fn tail_logs(
+        &self,
+        service_name: &str,
+        for_duration: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use tokio::io::AsyncBufReadExt;
+
+        let plist = self
+            .resolve_plist(service_name)
+            .and_then(|location| read_plist(&location.path));
+        let log_path = |key: &str| {
+            plist
+                .as_ref()
+                .and_then(|plist| plist.get(key)?.as_string())
+                .map(PathBuf::from)
+        };
+        let streams = match (log_path("StandardOutPath"), log_path("StandardErrorPath")) {
+            (None, None) => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "{} {service_name} sets no StandardOutPath or StandardErrorPath, following the unified log instead",
+                        icon(Icon::Notice)
+                    )
+                    .dimmed()
+                );
+                return self.follow_logs(service_name, for_duration).await;
+            }
+            (Some(out), Some(err)) if out == err => vec![("[out/err]", out)],
+            (out, err) => out
+                .map(|path| ("[out]", path))
+                .into_iter()
+                .chain(err.map(|path| ("[err]", path)))
+                .collect(),
+        };
+
+        let (sender, mut lines) = tokio::sync::mpsc::unbounded_channel();
+        let mut children = Vec::new();
+        for (tag, path) in streams {
+            let mut command = tokio::process::Command::new("tail");
+            command
+                .arg("-F")
+                .arg("-n")
+                .arg("10")
+                .arg(&path)
+                .stdout(Stdio::piped())
+                .kill_on_drop(true);
+            debug!(command = %describe_command(command.as_std()), "spawning tail");
+            let mut child = command.spawn()?;
+            let stdout = child.stdout.take().ok_or("tail has no stdout")?;
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let mut reader = tokio::io::BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = reader.next_line().await {
+                    if sender.send((tag, line)).is_err() {
+                        break;
+                    }
+                }
+            });
+            children.push(child);
+        }
+        drop(sender);
+
+        let deadline = async {
+            match for_duration {
+                Some(limit) => tokio::time::sleep(limit).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                _ = &mut deadline => {
+                    if let Some(limit) = for_duration {
+                        eprintln!(
+                            "{}",
+                            format!("{} Stopped after {}s", icon(Icon::Time), limit.as_secs()).dimmed()
+                        );
+                    }
+                    break;
+                }
+                line = lines.recv() => match line {
+                    Some((tag, line)) => {
+                        let tag = if tag == "[err]" { tag.red() } else { tag.dimmed() };
+                        println!("{tag} {line}");
+                    }
+                    // Every tail exited, e.g. because tail itself is missing.
+                    None => break,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn session_mismatch(&self, service_name: &str) -> Option<String> {
+        if !self.supports_bootstrap() {
+            return None;
+        }
+        let plist = read_plist(&self.resolve_plist(service_name)?.path)?;
+        let allowed = session_types(&plist);
+        if allowed.is_empty() {
+            return None;
+        }
+        let current = current_session_type()?;
+        if allowed
+            .iter()
+            .any(|session| session.eq_ignore_ascii_case(&current))
+        {
+            return None;
+        }
+        Some(format!(
+            "LimitLoadToSessionType is {} but the current session is {current}",
+            allowed.join(", ")
+        ))
+    }
+
+    fn launchd_target(&self, service_name: &str) -> PathBuf {
+        self.resolve_plist(service_name)
+            .map(|location| location.path)
+            .unwrap_or_else(|| PathBuf::from(service_name))
+    }
+
+    fn report(&self, message: ColoredString) {
+        let progress = self.progress.borrow();
+        if let Some(progress) = progress.as_ref() {
+            progress.clear();
+        }
+        // Keep stdout clean for JSON when a command emits machine-readable results.
+        if self.machine_output {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+        if let Some(progress) = progress.as_ref() {
+            progress.draw();
+        }
+    }
+
+    fn begin_progress(&self, action: &'static str, total: usize) {
+        let live = std::io::stdout().is_terminal()
+            && !self.machine_output
+            && !self.dry_run
+            && !self.explain;
+        *self.progress.borrow_mut() = Some(BatchProgress {
+            action,
+            total,
+            live,
+            active: Vec::new(),
+            results: Vec::new(),
+        });
+    }
+
+    async fn track<F>(&self, name: &str, operation: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: std::future::Future<Output = Result<(), Box<dyn std::error::Error>>>,
+    {
+        if let Some(progress) = self.progress.borrow_mut().as_mut() {
+            progress.active.push(name.to_string());
+            progress.draw();
+        }
+        let result = operation.await;
+        // Off a terminal each result is one plain line; release the borrow before reporting it.
+        let line = self.progress.borrow_mut().as_mut().and_then(|progress| {
+            progress.active.retain(|active| active != name);
+            let error = result.as_ref().err().map(|err| err.to_string());
+            progress.results.push((name.to_string(), error.clone()));
+            if progress.live {
+                progress.draw();
+                return None;
+            }
+            Some(match error {
+                Some(error) => format!(
+                    "{} {} {name}: {error}",
+                    progress.counter(),
+                    icon(Icon::Error)
+                )
+                .red(),
+                None => format!("{} {} {name}", progress.counter(), icon(Icon::Ok)).normal(),
+            })
+        });
+        if let Some(line) = line {
+            self.report(line);
+        }
+        result
+    }
+
+    fn finish_progress(&self, done: &str) {
+        let Some(progress) = self.progress.borrow_mut().take() else {
+            return;
+        };
+        progress.clear();
+        // The plain-line fallback already printed every result as it finished.
+        if !progress.live || progress.results.is_empty() {
+            return;
+        }
+        let width = progress
+            .results
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("SERVICE".len());
+        self.report(format!("{:<width$}  RESULT", "SERVICE").bold());
+        for (name, error) in &progress.results {
+            let outcome = match error {
+                Some(error) => format!("{} failed: {error}", icon(Icon::Error)).red(),
+                None => format!("{} {done}", icon(Icon::Ok)).green(),
+            };
+            self.report(format!("{name:<width$}  {outcome}").normal());
+        }
+    }
+
+    fn report_brew_output(&self, summary: ColoredString, stdout: &[u8]) {
+        let stdout = String::from_utf8_lossy(stdout);
+        match parse_brew_success_label(&stdout) {
+            Some(label) => {
+                self.report(format!("{summary} {}", format!("(label: {label})").dimmed()).normal())
+            }
+            None => {
+                self.report(summary);
+                // Unrecognised brew output may still carry useful detail, so pass it through.
+                if !stdout.trim().is_empty() {
+                    self.report(stdout.trim().dimmed());
+                }
+            }
+        }
+    }
+
+    fn skip_for_explain(
+        &self,
+        explanation: impl FnOnce() -> Result<String, Box<dyn std::error::Error>>,
+        commands: &[&Command],
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.explain {
+            return Ok(false);
+        }
+        self.report(format!("{} {}", icon(Icon::Hint), explanation()?).normal());
+        for command in commands {
+            self.report(format!("   $ {}", describe_command(command)).dimmed());
+        }
+        Ok(true)
+    }
+
+    // e.g. ("the user agent com.example.foo from ~/Library/LaunchAgents/com.example.foo.plist", "gui/501")
+    fn launchd_subject(
+        &self,
+        service_name: &str,
+    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+        match self.resolve_plist(service_name) {
+            Some(location) => Ok((
+                format!(
+                    "the {} {} from {}",
+                    plist_kind(&location.path),
+                    location.label,
+                    location.path.display()
+                ),
+                self.domain_target(location.domain)?,
+            )),
+            None => Ok((
+                format!("{service_name} (no plist found, so launchctl is given the bare name)"),
+                self.domain_target(LaunchDomain::Gui)?,
+            )),
+        }
+    }
+
+    fn warn_label_mismatch(&self, service_name: &str) {
+        let Some(location) = self.resolve_plist(service_name) else {
+            return;
+        };
+        let label = read_plist(&location.path)
+            .and_then(|plist| plist.get("Label")?.as_string().map(str::to_string));
+        if let Some(mismatch) = label.and_then(|label| label_mismatch(&location.path, &label)) {
+            self.report(format!("{} {mismatch}", icon(Icon::Warning)).yellow());
+        }
+    }
+
+    fn skip_all_for_dry_run(&self, commands: &[Command]) -> bool {
+        for command in commands {
+            self.skip_for_dry_run(command);
+        }
+        self.dry_run
+    }
+
+    fn skip_for_dry_run(&self, command: &Command) -> bool {
+        if self.dry_run {
+            self.report(
+                format!(
+                    "{} Would run: {}",
+                    icon(Icon::DryRun),
+                    describe_command(command)
+                )
+                .yellow(),
+            );
+        }
+        self.dry_run
+    }
+
+    fn attach_resources(&self, services: &mut [Service]) {
+        let pids: Vec<&str> = services.iter().filter_map(|s| s.pid.as_deref()).collect();
+        if pids.is_empty() {
+            return;
+        }
+
+        // One batched `ps` call for every PID rather than one process per service.
+        let Ok(output) = Command::new("ps")
+            .arg("-o")
+            .arg("pid=,%cpu=,rss=")
+            .arg("-p")
+            .arg(pids.join(","))
+            .traced_output()
+        else {
+            return;
+        };
+        let usage = parse_ps_resources(&String::from_utf8_lossy(&output.stdout));
+
+        for service in services.iter_mut() {
+            if let Some((cpu, rss)) = service.pid.as_ref().and_then(|pid| usage.get(pid)) {
+                service.cpu_percent = Some(*cpu);
+                service.rss_kb = Some(*rss);
+            }
+        }
+    }
+
+    fn process_uptimes(&self, services: &[Service]) -> HashMap<String, u64> {
+        let pids: Vec<&str> = services.iter().filter_map(|s| s.pid.as_deref()).collect();
+        if pids.is_empty() {
+            return HashMap::new();
+        }
+
+        let Ok(output) = Command::new("ps")
+            .arg("-o")
+            .arg("pid=,etime=")
+            .arg("-p")
+            .arg(pids.join(","))
+            .traced_output()
+        else {
+            return HashMap::new();
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (pid, etime) = line.trim().split_once(char::is_whitespace)?;
+                Some((pid.to_string(), parse_etime(etime)?))
+            })
+            .collect()
+    }
+
+    fn boot_time(&self) -> Option<u64> {
+        let output = Command::new("sysctl")
+            .arg("-n")
+            .arg("kern.boottime")
+            .traced_output()
+            .ok()?;
+        parse_boottime(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn process_elapsed(&self, pid: &str) -> Option<Duration> {
+        let output = Command::new("ps")
+            .arg("-o")
+            .arg("etime=")
+            .arg("-p")
+            .arg(pid)
+            .traced_output()
+            .ok()?;
+        let elapsed = parse_etime(str::from_utf8(&output.stdout).ok()?)?;
+        Some(Duration::from_secs(elapsed))
+    }
+
+    fn process_user(&self, pid: &str) -> Option<String> {
+        let output = Command::new("ps")
+            .arg("-o")
+            .arg("user=")
+            .arg("-p")
+            .arg(pid)
+            .traced_output()
+            .ok()?;
+        let user = str::from_utf8(&output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !user.is_empty()).then_some(user)
+    }
+
+    // A label launchd has loaded without any plist was bootstrapped at runtime, which is
+    // expected; a label launchd doesn't know either is most likely a typo or a deleted file.
+    async fn missing_plist_error(&self, service_name: &str) -> ServiceError {
+        let loaded = self
+            .list_launchd_services(&ServiceQuery::all())
+            .await
+            .is_ok_and(|services| {
+                services
+                    .iter()
+                    .any(|s| s.status != ServiceStatus::Disabled && s.is_named(service_name))
+            });
+        let name = service_name.to_string();
+        if loaded {
+            ServiceError::TransientService { name }
+        } else {
+            ServiceError::PlistNotFound { name }
+        }
+    }
+
+    async fn reload_service(&self, service_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(location) = self.resolve_plist(service_name) else {
+            return Err(self.missing_plist_error(service_name).await.into());
+        };
+        let domain = self.domain_target(location.domain)?;
+
+        let running = self
+            .list_launchd_services(&ServiceQuery::running())
+            .await?
+            .into_iter()
+            .find(|s| s.is_named(&location.label) || s.is_named(&location.path.to_string_lossy()));
+        let plist_changed = running
+            .as_ref()
+            .and_then(|s| s.pid.as_deref())
+            .and_then(|pid| self.process_elapsed(pid))
+            .zip(
+                std::fs::metadata(&location.path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok()),
+            )
+            .map(|(process_age, plist_age)| plist_age < process_age);
+
+        let privileged = location.domain == LaunchDomain::System;
+        let mut bootout = self.elevate(
+            self.bootout_command(&domain, &location.label, &location.path),
+            privileged,
+        );
+        let mut bootstrap =
+            self.elevate(self.bootstrap_command(&domain, &location.path), privileged);
+
+        let explained = self.skip_for_explain(
+            || {
+                Ok(format!(
+                    "This will boot out the {} {} from the {domain} domain, stopping it if it runs, then bootstrap it again from {} so launchd re-reads the plist",
+                    plist_kind(&location.path),
+                    location.label,
+                    location.path.display()
+                ))
+            },
+            &[&bootout, &bootstrap],
+        )?;
+        if explained {
+            return Ok(());
+        }
+        if self.dry_run {
+            self.skip_for_dry_run(&bootout);
+            self.skip_for_dry_run(&bootstrap);
+            return Ok(());
+        }
+
+        let output = bootout.traced_output_async().await?;
+        if output.status.success() {
+            // bootout returns before launchd has torn the job down, and bootstrapping a label
+            // that is still registered fails with an I/O error.
+            self.wait_until_unloaded(&location).await?;
+        } else if !bootout_not_loaded(&output) {
+            let error = str::from_utf8(&output.stderr)?;
+            return Err(ServiceError::command_failed("Failed to unload service", error).into());
+        }
+        let output = bootstrap.traced_output_async().await?;
+
+        if !output.status.success() {
+            let error = str::from_utf8(&output.stderr)?;
+            return Err(ServiceError::command_failed("Failed to reload service", error).into());
+        }
+
+        self.report(
+            format!(
+                "{} Launchd service '{}' reloaded from {}",
+                icon(Icon::Reload),
+                location.label,
+                location.path.display()
+            )
+            .green(),
+        );
+        match plist_changed {
+            Some(true) => self.report(
+                format!(
+                    "{} The plist changed on disk since the service was last started",
+                    icon(Icon::Note)
+                )
+                .yellow(),
+            ),
+            Some(false) => self.report(
+                format!(
+                    "{} The plist was unchanged since the service was last started",
+                    icon(Icon::Notice)
+                )
+                .dimmed(),
+            ),
+            None => {}
+        }
+        Ok(())
+    }
+
+    async fn watch_and_reload(&self, service_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(location) = self.resolve_plist(service_name) else {
+            return Err(self.missing_plist_error(service_name).await.into());
+        };
+        let dir = location
+            .path
+            .parent()
+            .ok_or_else(|| format!("{} has no parent directory", location.path.display()))?;
+
+        let (sender, mut changes) = tokio::sync::mpsc::unbounded_channel();
+        let file_name = location.path.file_name().map(|name| name.to_os_string());
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    let ours = event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == file_name.as_deref());
+                    if ours && !event.kind.is_access() {
+                        let _ = sender.send(());
+                    }
+                }
+            })?;
+        // Editors often save by renaming a temp file over the plist, which a file watch would miss.
+        watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+
+        println!(
+            "{}",
+            format!(
+                "{} Watching {} for changes (Ctrl-C to stop)",
+                icon(Icon::Watch),
+                location.path.display()
+            )
+            .blue()
+        );
+
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                change = changes.recv() => if change.is_none() { break },
+            }
+            // One save usually fires several events; wait for them to settle before reloading.
+            while let Ok(Some(())) = tokio::time::timeout(RELOAD_DEBOUNCE, changes.recv()).await {}
+
+            println!(
+                "[{}] {} changed, reloading",
+                chrono::Local::now().format("%H:%M:%S").to_string().dimmed(),
+                location.label.blue()
+            );
+            if let Err(err) = self.reload_service(service_name).await {
+                println!("{}", format!("{} {err}", icon(Icon::Error)).red());
+                continue;
+            }
+
+            let state = self
+                .list_launchd_services(&ServiceQuery::all())
+                .await?
+                .into_iter()
+                .find(|s| s.is_named(&location.label))
+                .map_or("not loaded".yellow().to_string(), |s| {
+                    let pid_info = s.pid.map_or("".to_string(), |p| format!(" (PID: {p})"));
+                    format!("{}{}", s.status.colored(), pid_info.cyan())
+                });
+            println!(
+                "[{}] {} - {state}",
+                chrono::Local::now().format("%H:%M:%S").to_string().dimmed(),
+                location.label.blue()
+            );
+        }
+        Ok(())
+    }
+
+    async fn set_service_enabled(
+        &self,
+        label: &str,
+        enable: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let domain = self
+            .resolve_plist(label)
+            .map_or(LaunchDomain::Gui, |location| location.domain);
+        let action = if enable { "enable" } else { "disable" };
+        if !self.supports_bootstrap() {
+            return Err(format!(
+                "launchctl {action} needs macOS {} or later, use `launchctl load -w`/`unload -w` instead",
+                MacosVersion::LAUNCHD_REWRITE
+            )
+            .into());
+        }
+
+        let mut command = Command::new("launchctl");
+        command
+            .arg(action)
+            .arg(format!("{}/{label}", self.domain_target(domain)?));
+        let mut command = self.elevate(command, domain == LaunchDomain::System);
+        let explained = self.skip_for_explain(
+            || {
+                let (subject, domain) = self.launchd_subject(label)?;
+                Ok(if enable {
+                    format!("This will clear the disabled override for {subject} in the {domain} domain, so launchd may load it in future sessions; it is not started now")
+                } else {
+                    format!("This will set a persistent disabled override for {subject} in the {domain} domain, so launchd will not load it in future sessions; a running instance keeps running")
+                })
+            },
+            &[&command],
+        )?;
+        if explained || self.skip_for_dry_run(&command) {
+            return Ok(());
+        }
+
+        let output = command.traced_output_async().await?;
+        if !output.status.success() {
+            let error = str::from_utf8(&output.stderr)?;
+            return Err(
+                ServiceError::command_failed(format!("Failed to {action} service"), error).into(),
+            );
+        }
+
+        if enable {
+            self.report(format!("{} Launchd service '{label}' enabled", icon(Icon::Ok)).green());
+        } else {
+            self.report(
+                format!("{} Launchd service '{label}' disabled", icon(Icon::Disable)).red(),
+            );
+        }
+        Ok(())
+    }
+
+    async fn set_services_enabled(
+        &self,
+        service: Option<&str>,
+        from_file: Option<&Path>,
+        enable: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = from_file else {
+            let label = service.ok_or("No service given")?;
+            return self.set_service_enabled(label, enable).await;
+        };
+        let labels = read_label_file(path)?;
+
+        let limit = self
+            .batch_limit(labels.iter().map(|label| (label.as_str(), false)))
+            .await;
+        let results = run_limited(&labels, limit, |label| {
+            self.set_service_enabled(label, enable)
+        })
+        .await;
+        let mut failed = 0;
+        for (label, result) in labels.iter().zip(results) {
+            if let Err(err) = result {
+                println!("{}", format!("{} {label}: {err}", icon(Icon::Error)).red());
+                failed += 1;
+            }
+        }
+
+        let action = if enable { "enabled" } else { "disabled" };
+        println!(
+            "{}",
+            format!(
+                "{} {} {action}, {failed} failed",
+                icon(Icon::Summary),
+                labels.len() - failed
+            )
+            .bold()
+        );
+        if failed > 0 {
+            return Err(format!(
+                "{failed} of {} services could not be {action}",
+                labels.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    // Deduplicated by label and sorted, so the output doesn't depend on directory order.
+    async fn search_plists(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<SearchResult>, Box<dyn std::error::Error>> {
+        let needle = pattern.to_lowercase();
+        let mut labels: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for file in scan_plist_dirs() {
+            let stem = file
+                .path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let label = file
+                .contents
+                .as_ref()
+                .ok()
+                .and_then(|plist| plist.get("Label")?.as_string().map(str::to_string))
+                .unwrap_or(stem.clone());
+            if label.to_lowercase().contains(&needle) || stem.to_lowercase().contains(&needle) {
+                labels.entry(label).or_default().push(file.path);
+            }
+        }
+
+        let loaded = self.list_launchd_services(&ServiceQuery::all()).await?;
+        Ok(labels
+            .into_iter()
+            .map(|(label, paths)| SearchResult {
+                loaded: loaded
+                    .iter()
+                    .any(|s| s.status != ServiceStatus::Disabled && s.is_named(&label)),
+                label,
+                paths,
+            })
+            .collect())
+    }
+
+    async fn lint_plists(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let files = scan_plist_dirs();
+        let mut problems = 0;
+        let mut labels: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+
+        for file in &files {
+            match &file.contents {
+                Ok(plist) => match plist.get("Label").and_then(|label| label.as_string()) {
+                    Some(label) => {
+                        if let Some(mismatch) = label_mismatch(&file.path, label) {
+                            problems += 1;
+                            println!("{}", format!("{} {mismatch}", icon(Icon::Warning)).yellow());
+                        }
+                        labels
+                            .entry(label.to_string())
+                            .or_default()
+                            .push(&file.path)
+                    }
+                    None => {
+                        problems += 1;
+                        println!(
+                            "{}",
+                            format!(
+                                "{} {}: missing Label key",
+                                icon(Icon::Warning),
+                                file.path.display()
+                            )
+                            .yellow()
+                        );
+                    }
+                },
+                Err(err) => {
+                    problems += 1;
+                    println!(
+                        "{}",
+                        format!(
+                            "{} {}: failed to parse: {err}",
+                            icon(Icon::Warning),
+                            file.path.display()
+                        )
+                        .yellow()
+                    );
+                }
+            }
+        }
+
+        for (label, paths) in labels.iter().filter(|(_, paths)| paths.len() > 1) {
+            problems += 1;
+            println!(
+                "{}",
+                format!(
+                    "{} Label '{label}' is defined in {} files:",
+                    icon(Icon::Duplicate),
+                    paths.len()
+                )
+                .red()
+            );
+            for path in paths {
+                println!("  {}", path.display());
+            }
+        }
+
+        if problems > 0 {
+            return Err(format!("{problems} problem(s) found in {} plists", files.len()).into());
+        }
+        println!(
+            "{}",
+            format!(
+                "{} No problems found in {} plists",
+                icon(Icon::Ok),
+                files.len()
+            )
+            .green()
+        );
+        Ok(())
+    }
+
+    async fn cleanup_orphaned_brew_plists(
+        &self,
+        assume_yes: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let orphans = find_orphaned_brew_plists(&scan_plist_dirs());
+
+        if orphans.is_empty() {
+            println!(
+                "{}",
+                format!("{} No orphaned brew plists found", icon(Icon::Ok)).green()
+            );
+            return Ok(());
+        }
+
+        let mut removed = 0;
+        for (path, label, program) in &orphans {
+            println!(
+                "{}",
+                format!(
+                    "{} {}: {program} no longer exists",
+                    icon(Icon::Orphan),
+                    path.display()
+                )
+                .yellow()
+            );
+            if !assume_yes && !self.dry_run && !self.explain {
+                guard_interactive_prompt();
+                let confirmed = prompt_result(
+                    Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!("Bootout '{label}' and remove its plist?"))
+                        .default(false)
+                        .interact(),
+                )?;
+                if !confirmed {
+                    continue;
+                }
+            }
+
+            let domain = self.domain_target(domain_for_path(path))?;
+            let mut bootout = self.bootout_command(&domain, label, path);
+            let explained = self.skip_for_explain(
+                || {
+                    Ok(format!(
+                        "This will boot out {label} in case it is still loaded, then delete {} because its program {program} no longer exists",
+                        path.display()
+                    ))
+                },
+                &[&bootout],
+            )?;
+            if explained {
+                continue;
+            }
+            if self.skip_for_dry_run(&bootout) {
+                self.report(
+                    format!("{} Would remove: {}", icon(Icon::DryRun), path.display()).yellow(),
+                );
+                continue;
+            }
+            // The job is usually not loaded any more, so a failed bootout is expected.
+            bootout.traced_output()?;
+            std::fs::remove_file(path)
+                .map_err(|err| format!("Failed to remove {}: {err}", path.display()))?;
+            self.report(format!("{} Removed {}", icon(Icon::Removed), path.display()).green());
+            removed += 1;
+        }
+
+        println!(
+            "{}",
+            format!(
+                "{} {removed} of {} orphaned plists removed",
+                icon(Icon::Summary),
+                orphans.len()
+            )
+            .bold()
+        );
+        Ok(())
+    }
+
+    async fn start_brewfile_group(
+        &self,
+        path: &Path,
+        assume_yes: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.brew_available() {
+            return Err(ServiceError::BrewUnavailable.into());
+        }
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let names = parse_brewfile(&contents);
+        if names.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "{} {} declares no restart_service/start_service formulae",
+                    icon(Icon::Empty),
+                    path.display()
+                )
+                .yellow()
+            );
+            return Ok(());
+        }
+
+        let services = self.list_brew_services(&ServiceQuery::all()).await?;
+        let mut pending = Vec::new();
+        for name in &names {
+            match services.iter().find(|s| &s.name == name) {
+                Some(service) if service.status == ServiceStatus::Running => println!(
+                    "{}",
+                    format!("{} {name}: already running", icon(Icon::Ok)).dimmed()
+                ),
+                Some(_) => pending.push(name.clone()),
+                None => println!(
+                    "{}",
+                    format!(
+                        "{} {name}: not installed, run `brew bundle --file {}` first",
+                        icon(Icon::Warning),
+                        path.display()
+                    )
+                    .yellow()
+                ),
+            }
+        }
+        if pending.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "{} Nothing to start from {}",
+                    icon(Icon::Ok),
+                    path.display()
+                )
+                .green()
+            );
+            return Ok(());
+        }
+
+        if !assume_yes && !self.dry_run && !self.explain {
+            guard_interactive_prompt();
+            let confirmed = prompt_result(
+                Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!(
+                        "{} Start {} from {}?",
+                        icon(Icon::Start),
+                        pending.join(", "),
+                        path.display()
+                    ))
+                    .default(true)
+                    .interact(),
+            )?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+
+        self.begin_progress("starting", pending.len());
+        let limit = self
+            .batch_limit(pending.iter().map(|name| (name.as_str(), true)))
+            .await;
+        let results = run_limited(&pending, limit, |name| {
+            self.track(name, self.start_service(name, true))
+        })
+        .await;
+        self.finish_progress("started");
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        println!(
+            "{}",
+            format!(
+                "{} {} started, {failed} failed",
+                icon(Icon::Summary),
+                pending.len() - failed
+            )
+            .bold()
+        );
+        if failed > 0 {
+            return Err(format!(
+                "{failed} of {} services could not be started",
+                pending.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn run_doctor(&self) -> DoctorReport {
+        let files = scan_plist_dirs();
+        let mut checks = vec![DoctorCheck {
+            check: "brew",
+            passed: true,
+            detail: match &self.brew_path {
+                Some(path) => format!("found at {}", path.display()),
+                None => "not installed, only launchd services can be managed".to_string(),
+            },
+            hint: None,
+        }];
+        checks.push(DoctorCheck {
+            check: "macos-version",
+            passed: true,
+            detail: match self.macos() {
+                Some(version) if version.supports_bootstrap() => {
+                    format!("macOS {version}, bootstrap/bootout available")
+                }
+                Some(version) => format!("macOS {version}, falling back to load/unload"),
+                None => "unknown (sw_vers not found), assuming a current macOS".to_string(),
+            },
+            hint: None,
+        });
+
+        let parse_errors: Vec<String> = files
+            .iter()
+            .filter_map(|file| match &file.contents {
+                Ok(plist) if plist.get("Label").is_some() => None,
+                Ok(_) => Some(format!("{}: missing Label key", file.path.display())),
+                Err(err) => Some(format!("{}: {err}", file.path.display())),
+            })
+            .collect();
+        checks.push(doctor_check(
+            "plist-parse",
+            parse_errors,
+            format!("{} plists parsed", files.len()),
+            "Fix or remove the listed plist files (plutil -lint shows details)",
+        ));
+
+        let mut labels: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+        for file in &files {
+            if let Some(label) = file
+                .contents
+                .as_ref()
+                .ok()
+                .and_then(|p| p.get("Label")?.as_string())
+            {
+                labels.entry(label).or_default().push(&file.path);
+            }
+        }
+        let mismatches: Vec<String> = labels
+            .iter()
+            .flat_map(|(label, paths)| paths.iter().filter_map(|path| label_mismatch(path, label)))
+            .collect();
+        checks.push(doctor_check(
+            "label-filename",
+            mismatches,
+            "every plist is named after its Label".to_string(),
+            "Rename the plist to <Label>.plist or fix its Label key",
+        ));
+
+        let duplicates: Vec<String> = labels
+            .iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(label, paths)| {
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                format!("{label} in {}", paths.join(", "))
+            })
+            .collect();
+        checks.push(doctor_check(
+            "duplicate-labels",
+            duplicates,
+            "every Label is defined once".to_string(),
+            "Keep one plist per Label, launchd only loads one of them",
+        ));
+
+        if let Some(current) = current_session_type() {
+            let mismatches: Vec<String> = files
+                .iter()
+                .filter(|file| domain_for_path(&file.path) == LaunchDomain::Gui)
+                .filter_map(|file| {
+                    let allowed = session_types(file.contents.as_ref().ok()?);
+                    let mismatch = !allowed.is_empty()
+                        && !allowed.iter().any(|s| s.eq_ignore_ascii_case(&current));
+                    mismatch.then(|| format!("{} ({})", file.path.display(), allowed.join(", ")))
+                })
+                .collect();
+            checks.push(doctor_check(
+                "session-types",
+                mismatches,
+                format!("all agents can load in the {current} session"),
+                "LimitLoadToSessionType must include the current session for the agent to load",
+            ));
+        }
+
+        let orphans: Vec<String> = find_orphaned_brew_plists(&files)
+            .into_iter()
+            .map(|(path, _, program)| format!("{} ({program} missing)", path.display()))
+            .collect();
+        checks.push(doctor_check(
+            "orphaned-brew-plists",
+            orphans,
+            "no brew plists point at missing programs".to_string(),
+            "Run `service-manager cleanup` to bootout and remove them",
+        ));
+
+        // Informational only: transient jobs are legitimate, but they can't be reloaded or linted.
+        if let Ok(services) = self.list_launchd_services(&ServiceQuery::all()).await {
+            let on_disk: HashSet<&str> = labels.keys().copied().collect();
+            // Disabled entries come from the override database and need not be loaded at all.
+            let transient: Vec<&str> = services
+                .iter()
+                .filter(|s| s.status != ServiceStatus::Disabled)
+                .map(Service::operation_name)
+                .filter(|label| !label.starts_with(SYSTEM_APPLE_PREFIX))
+                .filter(|label| !on_disk.contains(label) && self.resolve_plist(label).is_none())
+                .collect();
+            checks.push(DoctorCheck {
+                check: "transient-services",
+                passed: true,
+                detail: if transient.is_empty() {
+                    "every loaded service has a plist on disk".to_string()
+                } else {
+                    format!(
+                        "{} loaded without a plist on disk (transient/registered): {}",
+                        transient.len(),
+                        transient.join(", ")
+                    )
+                },
+                hint: None,
+            });
+        }
+
+        DoctorReport {
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+
+    async fn apply_operations(
+        &self,
+        file: Option<&Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let input = match file {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|err| format!("Failed to read {}: {err}", path.display()))?,
+            None => std::io::read_to_string(std::io::stdin())?,
+        };
+        let operations = parse_apply_operations(&input)?;
+
+        // Operations on the same service keep their document order (a stop before a start);
+        // different services run concurrently up to --concurrency.
+        let mut groups: Vec<Vec<(usize, &ApplyOperation)>> = Vec::new();
+        for entry in operations.iter().enumerate() {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].1.name == entry.1.name)
+            {
+                Some(group) => group.push(entry),
+                None => groups.push(vec![entry]),
+            }
+        }
+        let limit = self
+            .batch_limit(operations.iter().map(|operation| {
+                (
+                    operation.name.as_str(),
+                    operation.service_type == ServiceType::Brew,
+                )
+            }))
+            .await;
+        let grouped = run_limited(&groups, limit, |group| async move {
+            let mut outcomes = Vec::with_capacity(group.len());
+            for &(index, operation) in group {
+                outcomes.push((
+                    index,
+                    self.run_operation(operation.action, &operation.name, operation.service_type)
+                        .await,
+                ));
+            }
+            outcomes
+        })
+        .await;
+        let mut outcomes: Vec<_> = grouped.into_iter().flatten().collect();
+        outcomes.sort_by_key(|(index, _)| *index);
+        let outcomes = outcomes.into_iter().map(|(_, outcome)| outcome);
+        let results: Vec<ApplyResult> = operations
+            .into_iter()
+            .zip(outcomes)
+            .enumerate()
+            .map(|(index, (operation, outcome))| ApplyResult {
+                index,
+                action: operation.action,
+                name: operation.name,
+                service_type: operation.service_type,
+                success: outcome.is_ok(),
+                error: outcome.err().map(|err| err.to_string()),
+            })
+            .collect();
+
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        let failed = results.iter().filter(|r| !r.success).count();
+        if failed > 0 {
+            return Err(format!("{failed} of {} operations failed", results.len()).into());
+        }
+        Ok(())
+    }
+
+    async fn run_operation(
+        &self,
+        action: ApplyAction,
+        name: &str,
+        service_type: ServiceType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let is_brew = service_type == ServiceType::Brew;
+        match action {
+            ApplyAction::Start => self.start_service(name, is_brew).await,
+            ApplyAction::Stop => self.stop_service(name, is_brew).await,
+            ApplyAction::Enable => self.set_service_enabled(name, true).await,
+            ApplyAction::Disable => self.set_service_enabled(name, false).await,
+            ApplyAction::Reload => self.reload_service(name).await,
+        }
+    }
+
+    async fn export_services(
+        &self,
+        path: &Path,
+        include_brew: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut services = self.list_launchd_services(&ServiceQuery::all()).await?;
+        for service in &mut services {
+            service.plist_path = self
+                .resolve_plist(service.operation_name())
+                .map(|location| location.path);
+        }
+        if include_brew && self.brew_available() {
+            services.extend(self.list_brew_services(&ServiceQuery::all()).await?);
+        }
+
+        let json = serde_json::to_string_pretty(&services)?;
+        std::fs::write(path, json)
+            .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+        println!(
+            "{}",
+            format!(
+                "{} Exported {} services to {}",
+                icon(Icon::Snapshot),
+                services.len(),
+                path.display()
+            )
+            .green()
+        );
+        Ok(())
+    }
+
+    async fn import_services(
+        &self,
+        path: &Path,
+        apply: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let snapshot: Vec<Service> = serde_json::from_str(&contents)
+            .map_err(|err| format!("{} is not a service snapshot: {err}", path.display()))?;
+
+        let mut current = self.list_launchd_services(&ServiceQuery::all()).await?;
+        if snapshot.iter().any(|s| s.service_type == ServiceType::Brew) && self.brew_available() {
+            current.extend(self.list_brew_services(&ServiceQuery::all()).await?);
+        }
+        let current: HashMap<(ServiceType, &str), &Service> = current
+            .iter()
+            .map(|service| ((service.service_type, service.name.as_str()), service))
+            .collect();
+
+        // Only enabled/disabled and running state are restored; nothing is stopped.
+        let mut plan = Vec::new();
+        for wanted in &snapshot {
+            let now = current.get(&(wanted.service_type, wanted.name.as_str()));
+            let now_status = now.map(|service| &service.status);
+            let now_disabled = now_status == Some(&ServiceStatus::Disabled);
+            if wanted.service_type == ServiceType::Launchd {
+                if wanted.status == ServiceStatus::Disabled && !now_disabled {
+                    plan.push((ApplyAction::Disable, wanted));
+                } else if wanted.status != ServiceStatus::Disabled && now_disabled {
+                    plan.push((ApplyAction::Enable, wanted));
+                }
+            }
+            if wanted.status == ServiceStatus::Running
+                && now_status != Some(&ServiceStatus::Running)
+            {
+                plan.push((ApplyAction::Start, wanted));
+            }
+        }
+
+        if plan.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "{} Services already match {}",
+                    icon(Icon::Ok),
+                    path.display()
+                )
+                .green()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            format!(
+                "{} {} change(s) to match the snapshot:",
+                icon(Icon::Snapshot),
+                plan.len()
+            )
+            .bold()
+        );
+        for (action, service) in &plan {
+            let now = current
+                .get(&(service.service_type, service.name.as_str()))
+                .map_or("missing".to_string(), |s| s.status.to_string());
+            println!(
+                "  {:<8} {} [{}] (now {now}, snapshot {})",
+                format!("{action:?}").to_lowercase().cyan(),
+                service.name,
+                service.service_type.to_string().to_uppercase(),
+                service.status
+            );
+        }
+
+        if !apply {
+            println!("{}", "Re-run with --apply to make these changes".dimmed());
+            return Ok(());
+        }
+
+        let limit = self
+            .batch_limit(plan.iter().map(|(_, service)| {
+                (
+                    service.operation_name(),
+                    service.service_type == ServiceType::Brew,
+                )
+            }))
+            .await;
+        let results = run_limited(&plan, limit, |(action, service)| {
+            self.run_operation(*action, service.operation_name(), service.service_type)
+        })
+        .await;
+        let mut failed = 0;
+        for ((_, service), result) in plan.iter().zip(results) {
+            if let Err(err) = result {
+                println!(
+                    "{}",
+                    format!("{} {}: {err}", icon(Icon::Error), service.name).red()
+                );
+                failed += 1;
+            }
+        }
+        println!(
+            "{}",
+            format!(
+                "{} {} applied, {failed} failed",
+                icon(Icon::Summary),
+                plan.len() - failed
+            )
+            .bold()
+        );
+        if failed > 0 {
+            return Err(format!("{failed} of {} changes could not be applied", plan.len()).into());
+        }
+        Ok(())
+    }
+
+    async fn create_launch_agent(
+        &self,
+        label: &str,
+        program: &str,
+        args: &[String],
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        validate_label(label)?;
+        let program = resolve_executable(program)?;
+
+        let path = expand_home(PLIST_SEARCH_DIRS[0].0).join(format!("{label}.plist"));
+        if path.exists() && !force {
+            return Err(format!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            )
+            .into());
+        }
+
+        let agent = LaunchAgentPlist {
+            label: label.to_string(),
+            program_arguments: std::iter::once(program.to_string_lossy().to_string())
+                .chain(args.iter().cloned())
+                .collect(),
+            run_at_load: true,
+            keep_alive: true,
+        };
+
+        if self.skip_for_explain(
+            || {
+                Ok(format!(
+                    "This will write a user agent plist for {label} at {} that runs {} at login and restarts it whenever it exits; it is not loaded until you confirm",
+                    path.display(),
+                    program.display()
+                ))
+            },
+            &[],
+        )? {
+            return Ok(());
+        }
+        if self.dry_run {
+            let mut xml = Vec::new();
+            plist::to_writer_xml(&mut xml, &agent)?;
+            println!(
+                "{}",
+                format!("{} Would write {}:", icon(Icon::DryRun), path.display()).yellow()
+            );
+            println!("{}", String::from_utf8_lossy(&xml));
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        plist::to_file_xml(&path, &agent)?;
+        println!(
+            "{}",
+            format!(
+                "{} Created launch agent '{label}' at {}",
+                icon(Icon::Note),
+                path.display()
+            )
+            .green()
+        );
+
+        if !std::io::stdin().is_terminal() {
+            return Ok(());
+        }
+
+        guard_interactive_prompt();
+        let load_now = prompt_result(
+            Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} Load it now?", icon(Icon::Start)))
+                .default(false)
+                .interact(),
+        )?;
+        if load_now {
+            let domain = self.domain_target(LaunchDomain::Gui)?;
+            let output = self.bootstrap_command(&domain, &path).traced_output()?;
+            if !output.status.success() {
+                let error = str::from_utf8(&output.stderr)?;
+                return Err(ServiceError::command_failed("Failed to load service", error).into());
+            }
+            println!(
+                "{}",
+                format!(
+                    "{} Launchd service '{label}' loaded into {domain}",
+                    icon(Icon::Ok)
+                )
+                .green()
+            );
+        }
+        Ok(())
+    }
+
+    async fn interactive_start_service(
+        &self,
+        include_brew: bool,
+        pattern: Option<&str>,
+        wait: Option<Duration>,
+        mode: PickMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut all_services = Vec::new();
+
+        let launchd_services = self.list_launchd_services(&ServiceQuery::all()).await?;
+        all_services.extend(launchd_services);
+
+        if include_brew && self.brew_available() {
+            let brew_services = self.list_brew_services(&ServiceQuery::all()).await?;
+            all_services.extend(brew_services);
+        }
+
+        let query = ServiceQuery::all().pattern(pattern);
+        let stopped_services: Vec<&Service> = all_services
+            .iter()
+            .filter(|s| !matches!(s.status, ServiceStatus::Running | ServiceStatus::Disabled))
+            .filter(|s| query.matches(s))
+            .collect();
+
+        if stopped_services.is_empty() {
+            match pattern {
+                Some(p) => {
+                    eprintln!(
+                        "{}",
+                        format!("{} No stopped services match '{p}'", icon(Icon::Empty)).yellow()
+                    );
+                    exit_with(EXIT_NO_MATCH);
+                }
+                None => println!(
+                    "{}",
+                    format!("{} All services are already running!", icon(Icon::Ok)).green()
+                ),
+            }
+            return Ok(());
+        }
+
+        if let PickMode::All { force } = mode {
+            return self.run_all(&stopped_services, true, wait, force).await;
+        }
+
+        if pattern.is_some() && stopped_services.len() == 1 {
+            return self.start_and_wait(stopped_services[0], wait).await;
+        }
+
+        let service_names: Vec<String> = stopped_services
+            .iter()
+            .map(|s| format!("{} [{}]", s.name, s.service_type.to_string().to_uppercase()))
+            .collect();
+
+        ensure_candidates(&service_names)?;
+        guard_interactive_prompt();
+        if mode == PickMode::Multi {
+            let selections = prompt_result(
+                MultiSelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!(
+                        "{} Select the services you want to start (space to toggle):",
+                        icon(Icon::Start)
+                    ))
+                    .items(&service_names)
+                    .interact(),
+            )?;
+            let selected: Vec<&Service> = selections.iter().map(|&i| stopped_services[i]).collect();
+            return self.run_batch(&selected, true, wait).await;
+        }
+        let selection = prompt_result(
+            Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "{} Select the service you want to start:",
+                    icon(Icon::Start)
+                ))
+                .items(&service_names)
+                .interact(),
+        )?;
+
+        self.start_and_wait(stopped_services[selection], wait).await
+    }
+
+    async fn interactive_status(
+        &self,
+        include_brew: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut services = self.list_launchd_services(&ServiceQuery::all()).await?;
+        if include_brew && self.brew_available() {
+            services.extend(self.list_brew_services(&ServiceQuery::all()).await?);
+        }
+        if services.is_empty() {
+            println!(
+                "{}",
+                format!("{} No services found", icon(Icon::Empty)).yellow()
+            );
+            return Ok(());
+        }
+
+        let service_names: Vec<String> = services
+            .iter()
+            .map(|s| format!("{} [{}]", s.name, s.service_type.to_string().to_uppercase()))
+            .collect();
+        ensure_candidates(&service_names)?;
+        guard_interactive_prompt();
+        let selection = prompt_result(
+            Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "{} Select the service to inspect:",
+                    icon(Icon::Info)
+                ))
+                .items(&service_names)
+                .interact(),
+        )?;
+
+        let service = &services[selection];
+        self.get_service_status(
+            service.operation_name(),
+            service.service_type == ServiceType::Brew,
+            false,
+        )
+        .await
+    }
+
+    // The non-interactive start/stop path under --json: one exact service, one result line.
+    async fn direct_action(
+        &self,
+        action: ApplyAction,
+        service_name: &str,
+        include_brew: bool,
+        wait: Option<Duration>,
+        if_needed: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let found = self.find_exact_service(service_name, include_brew).await?;
+        let service_type = found.as_ref().map_or(
+            if include_brew {
+                ServiceType::Brew
+            } else {
+                ServiceType::Launchd
+            },
+            |service| service.service_type,
+        );
+        let outcome = match (action, &found) {
+            (ApplyAction::Start, _) if if_needed => {
+                self.start_service_if_needed(service_name, include_brew, wait)
+                    .await
+            }
+            (ApplyAction::Stop, _) if if_needed => {
+                self.stop_service_if_needed(service_name, include_brew, wait)
+                    .await
+            }
+            (_, None) => Err(ServiceError::ServiceNotFound {
+                name: service_name.to_string(),
+            }
+            .into()),
+            (ApplyAction::Start, Some(service)) => self.start_and_wait(service, wait).await,
+            (_, Some(service)) => self.stop_and_wait(service, wait).await,
+        };
+
+        let result = ActionResult {
+            action,
+            name: found.map_or_else(|| service_name.to_string(), |service| service.name),
+            service_type,
+            success: outcome.is_ok(),
+            error: outcome.as_ref().err().map(|err| err.to_string()),
+        };
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", serde_json::to_string(&result)?)?;
+        stdout.flush()?;
+        outcome
+    }
+
+    async fn find_exact_service(
+        &self,
+        service_name: &str,
+        include_brew: bool,
+    ) -> Result<Option<Service>, Box<dyn std::error::Error>> {
+        let mut services = self.list_launchd_services(&ServiceQuery::all()).await?;
+        if include_brew && self.brew_available() {
+            services.extend(self.list_brew_services(&ServiceQuery::all()).await?);
+        }
+        Ok(services.into_iter().find(|s| s.is_named(service_name)))
+    }
+
+    async fn check_services(
+        &self,
+        names: &[String],
+        include_brew: bool,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut services = self.list_launchd_services(&ServiceQuery::running()).await?;
+        if include_brew && self.brew_available() {
+            services.extend(self.list_brew_services(&ServiceQuery::running()).await?);
+        }
+        Ok(names
+            .iter()
+            .filter(|name| !services.iter().any(|s| s.is_named(name)))
+            .map(|name| format!("{name} down"))
+            .collect())
+    }
+
+    async fn start_service_if_needed(
+        &self,
+        service_name: &str,
+        include_brew: bool,
+        wait: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let service = self
+            .find_exact_service(service_name, include_brew)
+            .await?
+            .unwrap_or_else(|| Service {
+                name: service_name.to_string(),
+                status: ServiceStatus::Stopped,
+                pid: None,
+                service_type: if include_brew {
+                    ServiceType::Brew
+                } else {
+                    ServiceType::Launchd
+                },
+                last_exit_code: None,
+                cpu_percent: None,
+                rss_kb: None,
+                label: None,
+                plist_path: None,
+                user: None,
+            });
+
+        if service.status == ServiceStatus::Running {
+            self.report(format!("{} '{service_name}' is already running", icon(Icon::Ok)).green());
+            return Ok(());
+        }
+        self.start_and_wait(&service, wait).await
+    }
+
+    async fn stop_service_if_needed(
+        &self,
+        service_name: &str,
+        include_brew: bool,
+        wait: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.find_exact_service(service_name, include_brew).await? {
+            Some(service) if service.status == ServiceStatus::Running => {
+                self.stop_and_wait(&service, wait).await
+            }
+            _ => {
+                self.report(
+                    format!("{} '{service_name}' is already stopped", icon(Icon::Ok)).green(),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn run_all(
+        &self,
+        services: &[&Service],
+        start: bool,
+        wait: Option<Duration>,
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let verb = if start { "start" } else { "stop" };
+        println!(
+            "{}",
+            format!(
+                "{} {} services will {verb}:",
+                icon(if start { Icon::Start } else { Icon::Stop }),
+                services.len()
+            )
+            .bold()
+        );
+        for service in services {
+            println!(
+                "  {} [{}]",
+                service.name,
+                service.service_type.to_string().to_uppercase()
+            );
+        }
+
+        if !force && !self.dry_run && !self.explain {
+            guard_interactive_prompt();
+            let confirmed = prompt_result(
+                Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{verb} all {}?", services.len()))
+                    .default(false)
+                    .interact(),
+            )?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+        self.run_batch(services, start, wait).await
+    }
+
+    async fn run_batch(
+        &self,
+        services: &[&Service],
+        start: bool,
+        wait: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         if services.is_empty() {
-            println!("{}", "📭 No services found".yellow());
+            println!(
+                "{}",
+                format!("{} No services selected", icon(Icon::Empty)).yellow()
+            );
+            return Ok(());
+        }
+
+        // The picker may be stale by the time the user confirms, so re-check before acting.
+        let mut running = self.list_launchd_services(&ServiceQuery::running()).await?;
+        if services.iter().any(|s| s.service_type == ServiceType::Brew) && self.brew_available() {
+            self.invalidate_brew_cache();
+            running.extend(self.list_brew_services(&ServiceQuery::running()).await?);
+        }
+        let (pending, skipped): (Vec<&Service>, Vec<&Service>) =
+            services.iter().copied().partition(|service| {
+                let is_running = running.iter().any(|r| {
+                    r.service_type == service.service_type && r.is_named(service.operation_name())
+                });
+                is_running != start
+            });
+        for service in &skipped {
+            let state = if start { "running" } else { "stopped" };
+            println!(
+                "{}",
+                format!(
+                    "{} {}: already {state}, skipped",
+                    icon(Icon::Skipped),
+                    service.name
+                )
+                .dimmed()
+            );
+        }
+
+        let action = if start { "started" } else { "stopped" };
+        self.begin_progress(if start { "starting" } else { "stopping" }, pending.len());
+        let limit = self
+            .batch_limit(pending.iter().map(|service| {
+                (
+                    service.operation_name(),
+                    service.service_type == ServiceType::Brew,
+                )
+            }))
+            .await;
+        let results = run_limited(&pending, limit, |service| {
+            self.track(&service.name, async move {
+                if start {
+                    self.start_and_wait(service, wait).await
+                } else {
+                    self.stop_and_wait(service, wait).await
+                }
+            })
+        })
+        .await;
+        self.finish_progress(action);
+        let failed = results.iter().filter(|result| result.is_err()).count();
+
+        println!(
+            "{}",
+            format!(
+                "{} {} {action}, {failed} failed, {} skipped",
+                icon(Icon::Summary),
+                pending.len() - failed,
+                skipped.len()
+            )
+            .bold()
+        );
+        if failed > 0 {
+            return Err(format!(
+                "{failed} of {} services could not be {action}",
+                services.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn start_and_wait(
+        &self,
+        service: &Service,
+        wait: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let is_brew = service.service_type == ServiceType::Brew;
+        let attempts = self.start_attempts;
+        let mut attempt = 1;
+        loop {
+            if attempts > 1 {
+                self.report(
+                    format!(
+                        "{} Attempt {attempt}/{attempts}: starting '{}'",
+                        icon(Icon::Start),
+                        service.name
+                    )
+                    .dimmed(),
+                );
+            }
+            let result = match self.start_service(service.operation_name(), is_brew).await {
+                Err(err) => Err((err, false)),
+                Ok(()) => match wait {
+                    Some(timeout) if !self.dry_run && !self.explain => self
+                        .wait_until_running(
+                            service.operation_name(),
+                            is_brew,
+                            service.last_exit_code,
+                            timeout,
+                        )
+                        .await
+                        .map_err(|err| (err, true)),
+                    _ => Ok(()),
+                },
+            };
+            match result {
+                Err((err, loaded)) if attempt < attempts => {
+                    self.report(
+                        format!("{} Attempt {attempt} failed: {err}", icon(Icon::Warning)).yellow(),
+                    );
+                    // A job that loaded but then crashed must be unloaded before it can load again.
+                    if loaded {
+                        let _ = self.stop_service(service.operation_name(), is_brew).await;
+                    }
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(|(err, _)| err),
+            }
+        }
+    }
+
+    async fn stop_and_wait(
+        &self,
+        service: &Service,
+        wait: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let is_brew = service.service_type == ServiceType::Brew;
+        self.stop_service(service.operation_name(), is_brew).await?;
+        match wait {
+            Some(window) if !self.dry_run && !self.explain => {
+                self.wait_until_stopped(service.operation_name(), is_brew, window)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Unlike a start, a stop can look fine at first and be undone moments later by KeepAlive,
+    // so this watches the whole window instead of returning at the first stopped reading.
+    async fn wait_until_unloaded(
+        &self,
+        location: &PlistLocation,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = tokio::time::Instant::now() + UNLOAD_TIMEOUT;
+        let path = location.path.to_string_lossy();
+        loop {
+            let loaded = self
+                .list_launchd_services(&ServiceQuery::all())
+                .await?
+                .iter()
+                .any(|s| s.is_named(&location.label) || s.is_named(&path));
+            if !loaded {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!(
+                    "'{}' was still loaded {}s after bootout",
+                    location.label,
+                    UNLOAD_TIMEOUT.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn wait_until_stopped(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        window: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = tokio::time::Instant::now() + window;
+        loop {
+            let services = if is_brew {
+                self.invalidate_brew_cache();
+                self.list_brew_services(&ServiceQuery::running()).await?
+            } else {
+                self.list_launchd_services(&ServiceQuery::running()).await?
+            };
+            if let Some(service) = services.into_iter().find(|s| s.is_named(service_name)) {
+                let pid_info = service
+                    .pid
+                    .as_ref()
+                    .map_or("".to_string(), |p| format!(" (PID: {p})"));
+                return Err(format!(
+                    "'{service_name}' was running again{pid_info} within {}s of stopping; KeepAlive is probably relaunching it. Run `{BIN_NAME} disable {service_name}` first, then stop it",
+                    window.as_secs()
+                )
+                .into());
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                self.report(
+                    format!(
+                        "{} '{service_name}' stayed stopped for {}s",
+                        icon(Icon::Stop),
+                        window.as_secs()
+                    )
+                    .green(),
+                );
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    async fn wait_until_running(
+        &self,
+        service_name: &str,
+        is_brew: bool,
+        previous_exit_code: Option<i32>,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let services = if is_brew {
+                self.invalidate_brew_cache();
+                self.list_brew_services(&ServiceQuery::all()).await?
+            } else {
+                self.list_launchd_services(&ServiceQuery::all()).await?
+            };
+            let current = services.into_iter().find(|s| s.is_named(service_name));
+
+            if let Some(service) = &current {
+                if service.status == ServiceStatus::Running && (is_brew || service.pid.is_some()) {
+                    let pid_info = service
+                        .pid
+                        .as_ref()
+                        .map_or("".to_string(), |p| format!(" (PID: {p})"));
+                    self.report(
+                        format!(
+                            "{} '{service_name}' is running{pid_info}",
+                            icon(Icon::Running)
+                        )
+                        .green(),
+                    );
+                    return Ok(());
+                }
+                // A fresh nonzero exit code means the service crashed on startup.
+                if let Some(code) = service
+                    .last_exit_code
+                    .filter(|code| *code != 0 && Some(*code) != previous_exit_code)
+                {
+                    return Err(
+                        format!("'{service_name}' exited with code {code} after starting").into(),
+                    );
+                }
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!(
+                    "'{service_name}' did not reach running state within {}s",
+                    timeout.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    async fn interactive_stop_service(
+        &self,
+        include_brew: bool,
+        pattern: Option<&str>,
+        wait: Option<Duration>,
+        mode: PickMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut all_services = Vec::new();
+
+        let launchd_services = self.list_launchd_services(&ServiceQuery::all()).await?;
+        all_services.extend(launchd_services);
+
+        if include_brew && self.brew_available() {
+            let brew_services = self.list_brew_services(&ServiceQuery::all()).await?;
+            all_services.extend(brew_services);
+        }
+
+        let query = ServiceQuery::running().pattern(pattern);
+        let running_services: Vec<&Service> =
+            all_services.iter().filter(|s| query.matches(s)).collect();
+
+        if running_services.is_empty() {
+            match pattern {
+                Some(p) => {
+                    eprintln!(
+                        "{}",
+                        format!("{} No running services match '{p}'", icon(Icon::Empty)).yellow()
+                    );
+                    exit_with(EXIT_NO_MATCH);
+                }
+                None => println!(
+                    "{}",
+                    format!("{} No running services found!", icon(Icon::Stop)).red()
+                ),
+            }
+            return Ok(());
+        }
+
+        if let PickMode::All { force } = mode {
+            return self.run_all(&running_services, false, wait, force).await;
+        }
+
+        if pattern.is_some() && running_services.len() == 1 {
+            let service = running_services[0];
+            guard_interactive_prompt();
+            let confirmed = prompt_result(
+                Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{} Stop '{}'?", icon(Icon::Stop), service.name))
+                    .default(false)
+                    .interact(),
+            )?;
+            if !confirmed {
+                return Ok(());
+            }
+            return self.stop_and_wait(service, wait).await;
+        }
+
+        let service_names: Vec<String> = running_services
+            .iter()
+            .map(|s| {
+                // Running launchd rows always carry a PID; brew omits it for some services.
+                let pid_info = s
+                    .pid
+                    .as_ref()
+                    .map_or(String::new(), |pid| format!(" (PID: {pid})"));
+                format!(
+                    "{} [{}]{}",
+                    s.name,
+                    s.service_type.to_string().to_uppercase(),
+                    pid_info
+                )
+            })
+            .collect();
+
+        ensure_candidates(&service_names)?;
+        guard_interactive_prompt();
+        if mode == PickMode::Multi {
+            let selections = prompt_result(
+                MultiSelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!(
+                        "{} Select the services you want to stop (space to toggle):",
+                        icon(Icon::Stop)
+                    ))
+                    .items(&service_names)
+                    .interact(),
+            )?;
+            let selected: Vec<&Service> = selections.iter().map(|&i| running_services[i]).collect();
+            return self.run_batch(&selected, false, wait).await;
+        }
+        let selection = prompt_result(
+            Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "{} Select the service you want to stop:",
+                    icon(Icon::Stop)
+                ))
+                .items(&service_names)
+                .interact(),
+        )?;
+
+        self.stop_and_wait(running_services[selection], wait).await
+    }
+
+    fn print_services(&self, services: &[Service], display: &ListDisplay) {
+        let lines = self.render_services(services, display);
+        if lines.is_empty() {
+            return;
+        }
+        let output = lines.join("\n");
+        if display.pager && std::io::stdout().is_terminal() && page_output(&output) {
             return;
         }
+        println!("{output}");
+    }
+
+    fn render_services(&self, services: &[Service], display: &ListDisplay) -> Vec<String> {
+        if services.is_empty() {
+            if display.no_header {
+                return Vec::new();
+            }
+            return vec![format!("{} No services found", icon(Icon::Empty))
+                .yellow()
+                .to_string()];
+        }
+        if !display.columns.is_empty() {
+            return self.render_table(services, &display.columns, !display.no_header);
+        }
+
+        let mut lines = Vec::new();
+        if !display.no_header {
+            lines.push(
+                format!("{} System Services:", icon(Icon::Services))
+                    .bold()
+                    .blue()
+                    .to_string(),
+            );
+            lines.push("─".repeat(80).blue().to_string());
+        }
+
+        for service in services {
+            let type_badge = match service.service_type {
+                ServiceType::Brew => "[BREW]".magenta(),
+                ServiceType::Launchd => "[LAUNCHD]".cyan(),
+            };
+
+            let pid_info = service
+                .pid
+                .as_ref()
+                .map_or("".to_string(), |p| format!(" (PID: {p})"));
+
+            let exit_info = service
+                .last_exit_code
+                .filter(|code| *code != 0)
+                .map_or("".to_string(), |code| format!(" (exit {code})"));
+
+            let resource_info = if display.resources {
+                format_resources(service)
+            } else {
+                String::new()
+            };
+
+            lines.push(format!(
+                "{} {} {} - {}{}{}{}",
+                service.status.icon(),
+                type_badge,
+                self.tinted_name(service),
+                service.status.colored(),
+                pid_info.dimmed(),
+                exit_info.red(),
+                resource_info.cyan()
+            ));
+        }
+        if display.no_header {
+            return lines;
+        }
+
+        lines.push("─".repeat(80).blue().to_string());
+        let total = if services.len() < display.total {
+            format!(
+                "{} Showing {} of {} services",
+                icon(Icon::Summary),
+                services.len(),
+                display.total
+            )
+        } else {
+            format!(
+                "{} Total {} services listed",
+                icon(Icon::Summary),
+                services.len()
+            )
+        };
+        lines.push(total.bold().to_string());
+        if let Some(breakdown) = status_breakdown(services) {
+            lines.push(breakdown.dimmed().to_string());
+        }
+        if display.hidden > 0 {
+            lines.push(
+                format!(
+                    "{} {} hidden by --exclude",
+                    icon(Icon::Hidden),
+                    display.hidden
+                )
+                .dimmed()
+                .to_string(),
+            );
+        }
+        if display.hidden_apple > 0 {
+            lines.push(
+                format!(
+                    "{} {} {SYSTEM_APPLE_PREFIX}* services hidden (--include-system-apple shows them)",
+                    icon(Icon::Hidden),
+                    display.hidden_apple
+                )
+                .dimmed()
+                .to_string(),
+            );
+        }
+        lines
+    }
+
+    fn render_table(&self, services: &[Service], columns: &[Column], header: bool) -> Vec<String> {
+        let uptimes = if columns.contains(&Column::Uptime) {
+            self.process_uptimes(services)
+        } else {
+            HashMap::new()
+        };
+
+        let rows: Vec<Vec<String>> = services
+            .iter()
+            .map(|service| {
+                columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Name => service.name.clone(),
+                        Column::Status => service.status.to_string(),
+                        Column::Pid => service.pid.clone().unwrap_or_else(|| "-".to_string()),
+                        Column::Type => service.service_type.to_string(),
+                        Column::Uptime => service
+                            .pid
+                            .as_ref()
+                            .and_then(|pid| uptimes.get(pid))
+                            .map_or("-".to_string(), |seconds| format_uptime(*seconds)),
+                        Column::Cpu => service
+                            .cpu_percent
+                            .map_or("-".to_string(), |cpu| format!("{cpu:.1}%")),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let widths: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(column.header().len()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let pad = |cells: &[&str]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = Vec::new();
+        if header {
+            let headers: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+            lines.push(pad(&headers).bold().to_string());
+        }
+        for row in &rows {
+            let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            lines.push(pad(&cells));
+        }
+        lines
+    }
+}
+
+const EXIT_UNSUPPORTED_PLATFORM: i32 = 69;
+const VERIFY_ATTEMPTS: u32 = 5;
+const EXIT_DOCTOR_FAILED: i32 = 1;
+const EXIT_CHECK_CRITICAL: i32 = 2;
+const EXIT_STATUS_NOT_RUNNING: i32 = 1;
+const EXIT_NO_MATCH: i32 = 1;
+
+const BIN_NAME: &str = "service-manager";
+const DYNAMIC_COMPLETION_COMMANDS: [&str; 3] = ["status", "start", "stop"];
+
+// Live service names for the status/start/stop argument, on top of clap's static script.
+const BASH_DYNAMIC_COMPLETION: &str = r#"
+_service_manager_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ ${COMP_CWORD} -eq 2 && "${cur}" != -* ]]; then
+        case "${COMP_WORDS[1]}" in
+            status|start|stop)
+                local IFS=$'\n'
+                COMPREPLY=($(compgen -W "$(service-manager __complete-services 2>/dev/null)" -- "${cur}"))
+                return 0
+                ;;
+        esac
+    fi
+    _service__manager "$@"
+}
+complete -F _service_manager_dynamic -o nosort -o bashdefault -o default service-manager
+"#;
+
+const ZSH_DYNAMIC_COMPLETION: &str = r#"
+_service_manager_services() {
+    local -a services
+    services=(${(f)"$(service-manager __complete-services 2>/dev/null)"})
+    compadd -a services
+}
+"#;
+
+fn completion_script(shell: clap_complete::Shell) -> Result<String, Box<dyn std::error::Error>> {
+    let mut buffer = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, &mut buffer);
+    let script = String::from_utf8(buffer)?;
+    Ok(match shell {
+        clap_complete::Shell::Bash => script + BASH_DYNAMIC_COMPLETION,
+        clap_complete::Shell::Zsh => {
+            // clap leaves free-form positionals on `_default`; point the service ones at live names.
+            let mut subcommand = "";
+            let script: Vec<String> = script
+                .lines()
+                .map(|line| {
+                    if let Some(name) = line.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+                        subcommand = name;
+                    }
+                    let positional = line.starts_with("':") && line.ends_with(":_default' \\");
+                    if positional && DYNAMIC_COMPLETION_COMMANDS.contains(&subcommand) {
+                        line.replace(":_default' \\", ":_service_manager_services' \\")
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect();
+            // Keep `#compdef` first, and define the helper before the script's trailing dispatch.
+            let (compdef, rest) = script.split_first().ok_or("empty zsh completion script")?;
+            format!("{compdef}\n{ZSH_DYNAMIC_COMPLETION}{}\n", rest.join("\n"))
+        }
+        _ => script,
+    })
+}
 
-        println!("{}", "🔧 System Services:".bold().blue());
-        println!("{}", "─".repeat(80).blue());
+#[derive(Debug, Serialize)]
+struct ToolProbe {
+    name: &'static str,
+    path: Option<String>,
+    version: Option<String>,
+}
 
-        for service in services {
-            let status_icon = match service.status.as_str() {
-                "running" | "started" => "🟢",
-                "stopped" => "🔴",
-                _ => "🟡",
-            };
+#[derive(Debug, Serialize)]
+struct ProbeReport {
+    tools: Vec<ToolProbe>,
+    macos: Option<String>,
+    uid: Option<u32>,
+    domain: Option<String>,
+}
 
-            let status_color = match service.status.as_str() {
-                "running" | "started" => service.status.green(),
-                "stopped" => service.status.red(),
-                _ => service.status.yellow(),
-            };
+const PROBED_TOOLS: [&str; 5] = ["launchctl", "brew", "log", "plutil", "ps"];
+
+fn first_output_line(command: &mut Command) -> Option<String> {
+    let output = command.traced_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let line = str::from_utf8(&output.stdout).ok()?.lines().next()?.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
 
-            let type_badge = match service.service_type.as_str() {
-                "brew" => "[BREW]".magenta(),
-                "launchd" => "[LAUNCHD]".cyan(),
-                _ => "[UNKNOWN]".white(),
+fn probe_environment(uid_override: Option<u32>) -> ProbeReport {
+    let tools = PROBED_TOOLS
+        .iter()
+        .map(|&name| {
+            let path = if name == "brew" {
+                ServiceManager::check_brew_availability().map(|p| p.display().to_string())
+            } else {
+                first_output_line(Command::new("which").arg(name))
             };
+            // log, plutil and ps have no version flag; their presence is what matters.
+            let version = path.as_ref().and_then(|path| match name {
+                "launchctl" => first_output_line(Command::new(path).arg("version")),
+                "brew" => first_output_line(Command::new(path).arg("--version")),
+                _ => None,
+            });
+            ToolProbe {
+                name,
+                path,
+                version,
+            }
+        })
+        .collect();
+    let uid = uid_override.or_else(ServiceManager::current_uid);
+    ProbeReport {
+        tools,
+        macos: MacosVersion::detect().map(|version| version.to_string()),
+        uid,
+        domain: uid.map(|uid| format!("gui/{uid}")),
+    }
+}
 
-            let pid_info = service
-                .pid
-                .as_ref()
-                .map_or("".to_string(), |p| format!(" (PID: {p})"));
+fn print_probe(report: &ProbeReport) {
+    println!(
+        "{}",
+        format!("{} Environment probe", icon(Icon::Doctor))
+            .bold()
+            .blue()
+    );
+    for tool in &report.tools {
+        match &tool.path {
+            Some(path) => println!(
+                "{} {}: {path}{}",
+                icon(Icon::Ok),
+                tool.name.bold(),
+                tool.version
+                    .as_ref()
+                    .map_or(String::new(), |v| format!(" ({v})"))
+                    .dimmed()
+            ),
+            None => println!(
+                "{} {}: {}",
+                icon(Icon::Error),
+                tool.name.bold(),
+                "not found".red()
+            ),
+        }
+    }
+    let unknown = || "unknown".dimmed().to_string();
+    println!("  macOS:  {}", report.macos.clone().unwrap_or_else(unknown));
+    println!(
+        "  UID:    {}",
+        report.uid.map_or_else(unknown, |uid| uid.to_string())
+    );
+    println!(
+        "  Domain: {}",
+        report.domain.clone().unwrap_or_else(unknown)
+    );
+}
 
-            println!(
-                "{} {} {} - {}{}",
-                status_icon,
-                type_badge,
-                service.name.bold(),
-                status_color,
-                pid_info.dimmed()
+#[derive(Debug, Serialize)]
+struct CapabilityReport {
+    version: &'static str,
+    subcommands: Vec<String>,
+    macos: Option<String>,
+    backends: BTreeMap<&'static str, bool>,
+}
+
+fn capabilities() -> CapabilityReport {
+    let subcommands = Cli::command()
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    CapabilityReport {
+        version: env!("CARGO_PKG_VERSION"),
+        subcommands,
+        macos: MacosVersion::detect().map(|version| version.to_string()),
+        backends: BTreeMap::from([
+            ("launchd", launchctl_available()),
+            ("brew", ServiceManager::check_brew_availability().is_some()),
+        ]),
+    }
+}
+
+fn print_capabilities(report: &CapabilityReport) {
+    println!(
+        "{}",
+        format!("{} {BIN_NAME} {}", icon(Icon::Capabilities), report.version)
+            .bold()
+            .blue()
+    );
+    println!(
+        "  macOS:    {}",
+        report
+            .macos
+            .clone()
+            .unwrap_or_else(|| "unknown".dimmed().to_string())
+    );
+    for (backend, available) in &report.backends {
+        let state = if *available {
+            "available".green()
+        } else {
+            "unavailable".red()
+        };
+        println!("  {:<9} {state}", format!("{backend}:"));
+    }
+    println!("  Commands: {}", report.subcommands.join(", "));
+}
+
+fn launchctl_available() -> bool {
+    // Probe for launchctl itself rather than cfg!(target_os) so a shim works in CI.
+    Command::new("which")
+        .arg("launchctl")
+        .traced_output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+#[tokio::main]
+async fn main() {
+    STARTED.get_or_init(Instant::now);
+    let (cli, config) = parse_cli();
+    if cli.timings {
+        if let Ok(mut timings) = TIMINGS.lock() {
+            *timings = Some(Vec::new());
+        }
+    }
+    let json_errors = cli.json;
+    let result = match config {
+        Ok(config) => run(cli, config).await,
+        Err(err) => Err(err),
+    };
+    print_timings();
+    if let Err(err) = result {
+        if json_errors {
+            match serde_json::to_string(&ErrorReport::new(err.as_ref())) {
+                Ok(json) => eprintln!("{json}"),
+                Err(_) => eprintln!("Error: {err:?}"),
+            }
+        } else {
+            eprintln!("Error: {err:?}");
+        }
+        std::process::exit(1);
+    }
+}
+
+// Parses the command line, then re-parses it with the config's `[defaults.<subcommand>]`
+// entries spliced in as flags, so an explicit flag beats a config default, which beats
+// the built-in default. Config errors are handed back for `run` to report.
+fn parse_cli() -> (Cli, Result<Config, Box<dyn std::error::Error>>) {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    let matches = match Cli::command().try_get_matches_from(&args) {
+        Ok(matches) => matches,
+        // clap prints --version and exits before --json is seen, so catch that pairing here.
+        Err(err)
+            if err.kind() == clap::error::ErrorKind::DisplayVersion
+                && args.iter().any(|arg| arg == "--json") =>
+        {
+            match serde_json::to_string_pretty(&capabilities()) {
+                Ok(json) => println!("{json}"),
+                Err(err) => eprintln!("Error: {err:?}"),
+            }
+            std::process::exit(0);
+        }
+        Err(err) => err.exit(),
+    };
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => return (cli, Err(err)),
+    };
+    let defaults = match config_default_args(&config, &matches) {
+        Ok(defaults) if defaults.is_empty() => return (cli, Ok(config)),
+        Ok(defaults) => defaults,
+        Err(err) => return (cli, Err(err)),
+    };
+    let name = matches.subcommand_name().unwrap_or_default();
+    let Some(index) = subcommand_index(&args, name) else {
+        return (cli, Ok(config));
+    };
+    args.splice(index + 1..index + 1, defaults);
+    match Cli::try_parse_from(&args) {
+        Ok(cli) => (cli, Ok(config)),
+        Err(err) => {
+            let path = cli.config.unwrap_or_else(Config::default_path);
+            eprintln!(
+                "{}",
+                format!("Invalid [defaults.{name}] in {}", path.display()).red()
             );
+            err.exit()
         }
+    }
+}
 
-        println!("{}", "─".repeat(80).blue());
-        println!(
+fn config_default_args(
+    config: &Config,
+    matches: &clap::ArgMatches,
+) -> Result<Vec<OsString>, Box<dyn std::error::Error>> {
+    let Some((name, sub_matches)) = matches.subcommand() else {
+        return Ok(Vec::new());
+    };
+    let Some(defaults) = config.defaults.get(name) else {
+        return Ok(Vec::new());
+    };
+    let mut command = Cli::command();
+    command.build();
+    let Some(sub) = command.find_subcommand(name) else {
+        return Ok(Vec::new());
+    };
+    let given = |arg: &clap::Arg| {
+        sub_matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    };
+    // Conflicts are declared on one side only, so check both directions.
+    let conflicts = |a: &clap::Arg, b: &clap::Arg| {
+        sub.get_arg_conflicts_with(a)
+            .iter()
+            .any(|arg| arg.get_id() == b.get_id())
+    };
+    let mut args = Vec::new();
+    for (key, value) in defaults {
+        let id = key.replace('-', "_");
+        let arg = sub
+            .get_arguments()
+            .find(|arg| arg.get_id().as_str() == id && arg.get_long().is_some())
+            .ok_or_else(|| format!("Unknown option '{key}' in [defaults.{name}]"))?;
+        if given(arg)
+            || sub
+                .get_arguments()
+                .filter(|other| given(other))
+                .any(|other| conflicts(arg, other) || conflicts(other, arg))
+        {
+            continue;
+        }
+        let flag = OsString::from(format!("--{}", arg.get_long().unwrap_or_default()));
+        let takes_value = arg.get_action().takes_values();
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            match (value, takes_value) {
+                (toml::Value::Boolean(true), false) => args.push(flag.clone()),
+                (toml::Value::Boolean(false), false) => {}
+                (_, false) => {
+                    return Err(
+                        format!("'{key}' in [defaults.{name}] must be true or false").into(),
+                    )
+                }
+                (toml::Value::String(value), true) => {
+                    args.extend([flag.clone(), OsString::from(value)])
+                }
+                (
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_),
+                    true,
+                ) => args.extend([flag.clone(), OsString::from(value.to_string())]),
+                (_, true) => {
+                    return Err(format!(
+                        "'{key}' in [defaults.{name}] must be a string, number or list"
+                    )
+                    .into())
+                }
+            }
+        }
+    }
+    Ok(args)
+}
+
+// Finds the subcommand token, skipping over the values of global options before it.
+fn subcommand_index(args: &[OsString], name: &str) -> Option<usize> {
+    let command = Cli::command();
+    let takes_value: HashSet<String> = command
+        .get_arguments()
+        .filter(|arg| arg.get_action().takes_values())
+        .flat_map(|arg| {
+            let long = arg.get_long().map(|long| format!("--{long}"));
+            let short = arg.get_short().map(|short| format!("-{short}"));
+            long.into_iter().chain(short)
+        })
+        .collect();
+    let aliases: Vec<&str> = command
+        .find_subcommand(name)
+        .map(|sub| sub.get_all_aliases().collect())
+        .unwrap_or_default();
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].to_string_lossy();
+        if arg == name || aliases.contains(&arg.as_ref()) {
+            return Some(index);
+        }
+        if takes_value.contains(arg.as_ref()) {
+            index += 1;
+        }
+        index += 1;
+    }
+    None
+}
+
+fn direct_json_target(
+    pattern: Option<String>,
+    batch: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match pattern {
+        Some(name) if !batch => Ok(name),
+        _ => Err("--json needs a single service name; the interactive picker and --multi/--all can't print JSON".into()),
+    }
+}
+
+async fn run(cli: Cli, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(cli.log_level);
+    if let Commands::Completions { shell } = cli.command {
+        print!("{}", completion_script(shell)?);
+        return Ok(());
+    }
+    if cli.no_color {
+        colored::control::set_override(false);
+    }
+    NO_EMOJI.store(cli.no_emoji, Ordering::Relaxed);
+
+    // Runs before the launchctl check so it still reports on a machine that lacks it.
+    if let Commands::Capabilities = cli.command {
+        let report = capabilities();
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_capabilities(&report);
+        }
+        return Ok(());
+    }
+    if let Commands::Probe = cli.command {
+        let report = probe_environment(cli.uid);
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_probe(&report);
+        }
+        return Ok(());
+    }
+
+    if !launchctl_available() {
+        eprintln!(
             "{}",
-            format!("📊 Total {} services listed", services.len()).bold()
+            format!(
+                "{} launchctl not found. service-manager only supports macOS.",
+                icon(Icon::Error)
+            )
+            .red()
         );
+        exit_with(EXIT_UNSUPPORTED_PLATFORM);
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    let service_manager = ServiceManager::new();
+    let mut service_manager = ServiceManager::new(cli.dry_run, cli.uid)?;
+    service_manager.concurrency = usize::from(cli.concurrency);
+    service_manager.explain = cli.explain;
+    service_manager.config = config;
+    service_manager.show_alias = cli.alias;
+    service_manager.sudo = cli.sudo;
 
-    if !service_manager.brew_available {
-        println!(
+    if !service_manager.brew_available() {
+        eprintln!(
             "{}",
-            "⚠️  Brew not found. Only launchd services can be managed.".yellow()
+            format!(
+                "{} Brew not found. Only launchd services can be managed.",
+                icon(Icon::Warning)
+            )
+            .yellow()
         );
     }
 
+    match &cli.command {
+        Commands::List { .. }
+        | Commands::Status { watch: false, .. }
+        | Commands::Info { .. }
+        | Commands::Check { .. } => {
+            service_manager.cache_ttl = cli.cache_ttl.map(Duration::from_secs);
+        }
+        Commands::Start { .. }
+        | Commands::Stop { .. }
+        | Commands::Restart { .. }
+        | Commands::Reload { .. }
+        | Commands::Enable { .. }
+        | Commands::Disable { .. }
+        | Commands::Apply { .. }
+        | Commands::New { .. }
+        | Commands::Cleanup { .. }
+        | Commands::Group { .. }
+        | Commands::Import { apply: true, .. } => service_manager.clear_list_cache(),
+        _ => {}
+    }
+
     match cli.command {
-        Commands::List { running, brew } => {
+        Commands::List {
+            running,
+            brew,
+            pager,
+            format,
+            json_lines,
+            label_only,
+            print0,
+            resources,
+            sort,
+            limit,
+            only_failed,
+            include_available,
+            columns,
+            group_by,
+            porcelain,
+            no_header,
+            age,
+            boot_only,
+            boot_window,
+            exclude,
+            include_system_apple,
+            metric,
+        } => {
+            // --exclude is applied on top of --include-system-apple, so it can still hide
+            // individual Apple services; only the blanket com.apple. default is lifted.
+            let query = ServiceQuery::all()
+                .running_only(running)
+                .failed_only(only_failed)
+                .hide_available(!include_available)
+                .hide_system_apple(!include_system_apple)
+                .exclude(exclude);
+            if json_lines {
+                let mut remaining = limit.unwrap_or(usize::MAX);
+                let mut emit = |service: Service| {
+                    if remaining == 0 {
+                        return Ok(());
+                    }
+                    remaining -= 1;
+                    emit_json_line(service)
+                };
+                service_manager
+                    .stream_launchd_services(&query, &mut emit)
+                    .await?;
+                if brew && service_manager.brew_available() {
+                    service_manager
+                        .stream_brew_services(&query, &mut emit)
+                        .await?;
+                }
+                return Ok(());
+            }
+
+            // Fetch without the name filters first: brew entries borrow PIDs from launchd
+            // entries, and the footer reports how many services each filter hid.
+            let base = ServiceQuery::all().running_only(running);
             let mut all_services = Vec::new();
 
-            let launchd_services = service_manager.list_launchd_services(running).await?;
+            let launchd_services = service_manager.list_launchd_services(&base).await?;
             all_services.extend(launchd_services);
 
-            if brew && service_manager.brew_available {
-                let brew_services = service_manager.list_brew_services(running).await?;
+            if brew && service_manager.brew_available() {
+                let mut brew_services = service_manager.list_brew_services(&base).await?;
+                // A loaded brew service also shows up in `launchctl list` under its plist label;
+                // keep the brew entry but borrow the PID launchd knows about.
+                let brew_labels: HashSet<String> = brew_services
+                    .iter()
+                    .filter_map(|service| service.label.clone())
+                    .collect();
+                let mut launchd_pids = HashMap::new();
+                all_services.retain(|service| {
+                    if !brew_labels.contains(&service.name) {
+                        return true;
+                    }
+                    if let Some(pid) = &service.pid {
+                        launchd_pids.insert(service.name.clone(), pid.clone());
+                    }
+                    false
+                });
+                for service in &mut brew_services {
+                    if let Some(pid) = service.label.as_ref().and_then(|l| launchd_pids.get(l)) {
+                        service.pid.get_or_insert_with(|| pid.clone());
+                    }
+                }
                 all_services.extend(brew_services);
             }
 
-            service_manager.print_services(&all_services);
+            let hidden = all_services.iter().filter(|s| query.excludes(s)).count();
+            let hidden_apple = all_services
+                .iter()
+                .filter(|s| !query.excludes(s) && query.hides_apple(s))
+                .count();
+            all_services.retain(|service| query.matches(service));
+
+            if only_failed && all_services.is_empty() && !cli.json && !porcelain && !no_header {
+                println!(
+                    "{}",
+                    format!("{} No failed services", icon(Icon::Ok)).green()
+                );
+                return Ok(());
+            }
+
+            if let Some(age) = age {
+                // Services without a PID have no process age, so they never match.
+                let uptimes = service_manager.process_uptimes(&all_services);
+                all_services.retain(|service| {
+                    service
+                        .pid
+                        .as_ref()
+                        .and_then(|pid| uptimes.get(pid))
+                        .is_some_and(|seconds| *seconds <= age.as_secs())
+                });
+            }
+
+            if boot_only {
+                let boot_time = service_manager
+                    .boot_time()
+                    .ok_or("Could not read the boot time from sysctl kern.boottime")?;
+                let uptimes = service_manager.process_uptimes(&all_services);
+                all_services.retain(|service| {
+                    service
+                        .pid
+                        .as_ref()
+                        .and_then(|pid| uptimes.get(pid))
+                        .and_then(|uptime| seconds_since_boot_at_start(boot_time, *uptime))
+                        .is_some_and(|after_boot| after_boot <= boot_window)
+                });
+            }
+
+            if resources
+                || matches!(sort, Some(SortKey::Cpu | SortKey::Mem))
+                || columns.contains(&Column::Cpu)
+            {
+                service_manager.attach_resources(&mut all_services);
+            }
+            if let Some(key) = sort {
+                sort_services(&mut all_services, key);
+            }
+
+            let total = all_services.len();
+            if metric {
+                let gauge = ServiceGauge::new(&all_services);
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&gauge)?);
+                } else {
+                    print!("{}", gauge.to_prometheus());
+                }
+                return Ok(());
+            }
+            if let Some(limit) = limit {
+                all_services.truncate(limit);
+            }
+
+            if cli.json {
+                // stdout stays a flat array (or object with --group-by), so the count goes to stderr.
+                if all_services.len() < total {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "{} Showing {} of {total} services (--limit)",
+                            icon(Icon::Summary),
+                            all_services.len()
+                        )
+                        .dimmed()
+                    );
+                }
+                let json = match group_by {
+                    Some(GroupBy::Type) => {
+                        let mut groups: BTreeMap<ServiceType, Vec<Service>> = [
+                            (ServiceType::Launchd, Vec::new()),
+                            (ServiceType::Brew, Vec::new()),
+                        ]
+                        .into_iter()
+                        .collect();
+                        for service in all_services {
+                            groups
+                                .entry(service.service_type)
+                                .or_default()
+                                .push(service);
+                        }
+                        serde_json::to_string_pretty(&groups)?
+                    }
+                    None => serde_json::to_string_pretty(&all_services)?,
+                };
+                println!("{json}");
+                return Ok(());
+            }
+
+            if porcelain {
+                // Stable contract: no header, colors or totals, and an empty pid field when absent.
+                let mut stdout = std::io::stdout().lock();
+                for service in &all_services {
+                    writeln!(
+                        stdout,
+                        "{}\t{}\t{}\t{}",
+                        service.name,
+                        service.status,
+                        service.pid.as_deref().unwrap_or(""),
+                        service.service_type
+                    )?;
+                }
+                stdout.flush()?;
+                return Ok(());
+            }
+
+            if label_only {
+                let separator = if print0 { "\0" } else { "\n" };
+                let mut stdout = std::io::stdout().lock();
+                for service in &all_services {
+                    write!(stdout, "{}{separator}", service.operation_name())?;
+                }
+                stdout.flush()?;
+                return Ok(());
+            }
+
+            match format {
+                Some(template) => {
+                    for service in &all_services {
+                        println!("{}", render_format_template(&template, service));
+                    }
+                }
+                None => service_manager.print_services(
+                    &all_services,
+                    &ListDisplay {
+                        pager,
+                        resources,
+                        total,
+                        hidden,
+                        hidden_apple,
+                        columns,
+                        no_header,
+                    },
+                ),
+            }
+        }
+        Commands::Start {
+            pattern,
+            brew,
+            wait,
+            retry_until_running,
+            if_needed,
+            multi,
+            all,
+            filter,
+            force,
+            verify,
+            extra_args,
+        } => {
+            service_manager.passthrough_args = extra_args;
+            service_manager.verify = verify;
+            service_manager.start_attempts = retry_until_running.unwrap_or(1);
+            let wait = wait.map(Duration::from_secs);
+            let pattern = pattern
+                .or(filter)
+                .map(|p| service_manager.config.resolve_alias(&p));
+            if all && pattern.is_none() {
+                return Err("--all needs a pattern or --filter".into());
+            }
+            if cli.json {
+                let name = direct_json_target(pattern, multi || all)?;
+                service_manager.machine_output = true;
+                return service_manager
+                    .direct_action(ApplyAction::Start, &name, brew, wait, if_needed)
+                    .await;
+            }
+            match pattern {
+                Some(name) if if_needed => {
+                    service_manager
+                        .start_service_if_needed(&name, brew, wait)
+                        .await?
+                }
+                pattern => {
+                    service_manager
+                        .interactive_start_service(
+                            brew,
+                            pattern.as_deref(),
+                            wait,
+                            PickMode::new(multi, all, force),
+                        )
+                        .await?
+                }
+            }
+        }
+        Commands::Stop {
+            pattern,
+            brew,
+            if_needed,
+            wait,
+            multi,
+            all,
+            filter,
+            force,
+            verify,
+            extra_args,
+        } => {
+            service_manager.passthrough_args = extra_args;
+            service_manager.verify = verify;
+            let wait = wait.map(Duration::from_secs);
+            let pattern = pattern
+                .or(filter)
+                .map(|p| service_manager.config.resolve_alias(&p));
+            if all && pattern.is_none() {
+                return Err("--all needs a pattern or --filter".into());
+            }
+            if cli.json {
+                let name = direct_json_target(pattern, multi || all)?;
+                service_manager.machine_output = true;
+                return service_manager
+                    .direct_action(ApplyAction::Stop, &name, brew, wait, if_needed)
+                    .await;
+            }
+            match pattern {
+                Some(name) if if_needed => {
+                    service_manager
+                        .stop_service_if_needed(&name, brew, wait)
+                        .await?
+                }
+                pattern => {
+                    service_manager
+                        .interactive_stop_service(
+                            brew,
+                            pattern.as_deref(),
+                            wait,
+                            PickMode::new(multi, all, force),
+                        )
+                        .await?
+                }
+            }
+        }
+        Commands::Status {
+            service,
+            brew,
+            first_match,
+            watch,
+            interval,
+            notify,
+            oneline,
+        } => match service.map(|s| service_manager.config.resolve_alias(&s)) {
+            None => service_manager.interactive_status(brew).await?,
+            Some(service) if oneline => {
+                let status = service_manager.oneline_status(&service, brew).await?;
+                println!("{} {service}", status.icon());
+                if status != ServiceStatus::Running {
+                    exit_with(EXIT_STATUS_NOT_RUNNING);
+                }
+            }
+            Some(service) if watch => {
+                service_manager.machine_output = cli.json;
+                service_manager
+                    .watch_service_status(&service, brew, interval, notify)
+                    .await?
+            }
+            Some(service) => {
+                service_manager
+                    .get_service_status(&service, brew, first_match)
+                    .await?
+            }
+        },
+        Commands::Check { services, brew } => {
+            let down = service_manager.check_services(&services, brew).await?;
+            if down.is_empty() {
+                println!("OK");
+            } else {
+                println!("CRITICAL: {}", down.join(", "));
+                exit_with(EXIT_CHECK_CRITICAL);
+            }
+        }
+        Commands::Top { sort, interval } => {
+            service_manager.show_top(sort, interval).await?;
+        }
+        Commands::Info {
+            service,
+            brew,
+            show_secrets,
+            key,
+        } => {
+            service_manager.machine_output = cli.json && key.is_none();
+            service_manager
+                .show_info(&service, brew, show_secrets, key.as_deref())
+                .await?;
+        }
+        Commands::Logs {
+            service,
+            since,
+            follow,
+            tail,
+            for_duration,
+        } => {
+            if tail {
+                service_manager.tail_logs(&service, for_duration).await?;
+            } else if follow {
+                service_manager.follow_logs(&service, for_duration).await?;
+            } else {
+                service_manager.show_logs(&service, &since).await?;
+            }
+        }
+        Commands::Restart { service, brew } => {
+            let service = service_manager.config.resolve_alias(&service);
+            service_manager.restart_service(&service, brew).await?;
+        }
+        Commands::Reload { service, watch } => {
+            if watch {
+                service_manager.watch_and_reload(&service).await?;
+            } else {
+                service_manager.reload_service(&service).await?;
+            }
+        }
+        Commands::Enable { service, from_file } => {
+            service_manager
+                .set_services_enabled(service.as_deref(), from_file.as_deref(), true)
+                .await?;
+        }
+        Commands::Disable { service, from_file } => {
+            service_manager
+                .set_services_enabled(service.as_deref(), from_file.as_deref(), false)
+                .await?;
+        }
+        Commands::Lint => {
+            service_manager.lint_plists().await?;
+        }
+        Commands::Search { pattern } => {
+            let results = service_manager.search_plists(&pattern).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else {
+                print_search_results(&pattern, &results);
+            }
+        }
+        Commands::Export { path, brew } => {
+            service_manager.export_services(&path, brew).await?;
+        }
+        Commands::Import { path, apply } => {
+            service_manager.import_services(&path, apply).await?;
+        }
+        Commands::Completions { .. } => unreachable!("handled before launchctl detection"),
+        Commands::CompleteServices { brew } => {
+            let mut stdout = std::io::stdout().lock();
+            for service in service_manager
+                .list_launchd_services(&ServiceQuery::all())
+                .await?
+            {
+                writeln!(stdout, "{}", service.operation_name())?;
+            }
+            if brew && service_manager.brew_available() {
+                for service in service_manager
+                    .list_brew_services(&ServiceQuery::all())
+                    .await?
+                {
+                    writeln!(stdout, "{}", service.name)?;
+                }
+            }
+        }
+        Commands::Doctor => {
+            let report = service_manager.run_doctor().await;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!(
+                    "{}",
+                    format!("{} Service Manager Doctor", icon(Icon::Doctor))
+                        .bold()
+                        .blue()
+                );
+                for check in &report.checks {
+                    let marker = if check.passed {
+                        icon(Icon::Ok)
+                    } else {
+                        icon(Icon::Error)
+                    };
+                    println!("{marker} {}: {}", check.check.bold(), check.detail);
+                    if let Some(hint) = &check.hint {
+                        println!("   {} {}", icon(Icon::Hint), hint.yellow());
+                    }
+                }
+            }
+            if !report.passed {
+                exit_with(EXIT_DOCTOR_FAILED);
+            }
+        }
+        Commands::Probe | Commands::Capabilities => {
+            unreachable!("handled before the launchctl check")
         }
-        Commands::Start { brew } => {
-            service_manager.interactive_start_service(brew).await?;
+        Commands::Cleanup { yes } => {
+            service_manager.cleanup_orphaned_brew_plists(yes).await?;
         }
-        Commands::Stop { brew } => {
-            service_manager.interactive_stop_service(brew).await?;
+        Commands::Group { brewfile, yes } => {
+            service_manager.start_brewfile_group(&brewfile, yes).await?;
         }
-        Commands::Status { service, brew } => {
-            service_manager.get_service_status(&service, brew).await?;
+        Commands::Apply { file } => {
+            service_manager.machine_output = true;
+            service_manager.apply_operations(file.as_deref()).await?;
+        }
+        Commands::New {
+            label,
+            program,
+            args,
+            force,
+        } => {
+            service_manager
+                .create_launch_agent(&label, &program, &args, force)
+                .await?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `launchctl list` in the layout macOS 10.15 (Catalina) prints.
+    const CATALINA_LIST: &str = "PID\tStatus\tLabel
+-\t0\tcom.apple.SafariHistoryServiceAgent
+417\t0\tcom.apple.Finder
+-\t0\tcom.apple.homed
+1024\t0\tcom.apple.bird
+-\t78\thomebrew.mxcl.postgresql
+";
+
+    // `launchctl list` in the layout macOS 14 (Sonoma) prints, including signal exits and
+    // per-process application.* jobs.
+    const SONOMA_LIST: &str = "PID\tStatus\tLabel
+-\t0\tcom.apple.SafariHistoryServiceAgent
+689\t0\tcom.apple.Finder
+-\t-9\tcom.apple.cloudphotod
+3312\t0\tapplication.com.apple.Terminal.2140721.2140727
+-\t0\thomebrew.mxcl.redis
+";
+
+    fn entries(output: &str) -> Vec<(Option<String>, Option<i32>, String)> {
+        parse_launchctl_list(output)
+            .map(|entry| (entry.pid, entry.last_exit_code, entry.label))
+            .collect()
+    }
+
+    fn entry(pid: Option<&str>, status: i32, label: &str) -> (Option<String>, Option<i32>, String) {
+        (pid.map(str::to_string), Some(status), label.to_string())
+    }
+
+    #[test]
+    fn parses_catalina_list() {
+        assert_eq!(
+            entries(CATALINA_LIST),
+            vec![
+                entry(None, 0, "com.apple.SafariHistoryServiceAgent"),
+                entry(Some("417"), 0, "com.apple.Finder"),
+                entry(None, 0, "com.apple.homed"),
+                entry(Some("1024"), 0, "com.apple.bird"),
+                entry(None, 78, "homebrew.mxcl.postgresql"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_sonoma_list() {
+        assert_eq!(
+            entries(SONOMA_LIST),
+            vec![
+                entry(None, 0, "com.apple.SafariHistoryServiceAgent"),
+                entry(Some("689"), 0, "com.apple.Finder"),
+                entry(None, -9, "com.apple.cloudphotod"),
+                entry(
+                    Some("3312"),
+                    0,
+                    "application.com.apple.Terminal.2140721.2140727"
+                ),
+                entry(None, 0, "homebrew.mxcl.redis"),
+            ]
+        );
+    }
+
+    #[test]
+    fn cleans_path_style_labels() {
+        let cases = [
+            (
+                "/Users/me/Library/LaunchAgents/com.example.foo.plist",
+                Some("com.example.foo"),
+            ),
+            ("/usr/local/libexec/example-helper", Some("example-helper")),
+            ("com.example.foo", None),
+            ("homebrew.mxcl.nginx", None),
+        ];
+        for (label, cleaned) in cases {
+            assert_eq!(
+                clean_launchd_label(label).as_deref(),
+                cleaned,
+                "label {label:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_spaces_inside_labels() {
+        assert_eq!(
+            entries(
+                "PID\tStatus\tLabel\n812\t0\tcom.example.My Sync Agent\n-\t0\tcom.example.plain\n"
+            ),
+            vec![
+                entry(Some("812"), 0, "com.example.My Sync Agent"),
+                entry(None, 0, "com.example.plain"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_output_has_no_entries() {
+        assert!(entries("").is_empty());
+        assert!(entries("\n\n").is_empty());
+    }
+
+    #[test]
+    fn header_only_output_has_no_entries() {
+        assert!(entries("PID\tStatus\tLabel\n").is_empty());
+        assert!(entries("PID\tStatus\tLabel").is_empty());
+    }
+
+    #[test]
+    fn headerless_output_falls_back_to_default_columns() {
+        assert_eq!(
+            entries("123\t0\tcom.example.running\n\n-\t78\tcom.example.crashed\n"),
+            vec![
+                entry(Some("123"), 0, "com.example.running"),
+                entry(None, 78, "com.example.crashed"),
+            ]
+        );
+        assert_eq!(
+            entries("12 0 com.x.y\n- 0 com.x.z\n"),
+            vec![entry(Some("12"), 0, "com.x.y"), entry(None, 0, "com.x.z")]
+        );
+    }
+
+    #[test]
+    fn icon_glyphs_and_tags_are_unique() {
+        let glyphs: HashSet<&str> = Icon::ALL.iter().map(|icon| icon.glyph()).collect();
+        let tags: HashSet<&str> = Icon::ALL.iter().map(|icon| icon.tag()).collect();
+        assert_eq!(glyphs.len(), Icon::ALL.len(), "duplicate glyph");
+        assert_eq!(tags.len(), Icon::ALL.len(), "duplicate tag");
+    }
+
+    fn service(name: &str, pid: Option<&str>) -> Service {
+        Service {
+            name: name.to_string(),
+            status: ServiceStatus::from_launchd(pid),
+            pid: pid.map(str::to_string),
+            service_type: ServiceType::Launchd,
+            last_exit_code: Some(0),
+            cpu_percent: None,
+            rss_kb: None,
+            label: None,
+            plist_path: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn parses_format_templates() {
+        assert_eq!(
+            parse_format_template("{name}\\t{pid}\\n").as_deref(),
+            Ok("{name}\t{pid}\n")
+        );
+        assert_eq!(parse_format_template("plain").as_deref(), Ok("plain"));
+        assert!(parse_format_template("{name")
+            .unwrap_err()
+            .contains("unclosed"));
+        assert!(parse_format_template("{label}")
+            .unwrap_err()
+            .contains("unknown placeholder '{label}'"));
+    }
+
+    #[test]
+    fn renders_format_templates_in_one_pass() {
+        let running = service("com.example.agent", Some("42"));
+        assert_eq!(
+            render_format_template("{name} {status} {pid} {type}", &running),
+            "com.example.agent running 42 launchd"
+        );
+        assert_eq!(
+            render_format_template("[{pid}]", &service("com.example.idle", None)),
+            "[]"
+        );
+        assert_eq!(
+            render_format_template("{name}={status}", &service("odd.{status}", None)),
+            "odd.{status}=stopped"
+        );
+    }
+
+    fn default_args(defaults: &str, argv: &[&str]) -> Result<Vec<String>, String> {
+        let config = Config {
+            defaults: toml::from_str(defaults).unwrap(),
+            ..Config::default()
+        };
+        let matches = Cli::command().try_get_matches_from(argv).unwrap();
+        config_default_args(&config, &matches)
+            .map(|args| {
+                args.into_iter()
+                    .map(|arg| arg.into_string().unwrap())
+                    .collect()
+            })
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn explicit_flag_beats_config_default() {
+        let defaults = "[list]\nlimit = 5\n";
+        assert_eq!(
+            default_args(defaults, &["service-manager", "list"]),
+            Ok(vec!["--limit".to_string(), "5".to_string()])
+        );
+        assert_eq!(
+            default_args(defaults, &["service-manager", "list", "--limit", "2"]),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn conflicting_flag_suppresses_config_default() {
+        // The conflict is declared on --json-lines, so this checks the reverse direction.
+        assert_eq!(
+            default_args(
+                "[list]\nformat = \"{name}\"\n",
+                &["service-manager", "list", "--json-lines"]
+            ),
+            Ok(vec![])
+        );
+        assert_eq!(
+            default_args(
+                "[list]\njson-lines = true\n",
+                &["service-manager", "list", "--format", "{name}"]
+            ),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn expands_array_and_boolean_defaults() {
+        assert_eq!(
+            default_args(
+                "[list]\nexclude = [\"com.apple.\", \"re:^org\\\\.\"]\nrunning = true\nbrew = false\n",
+                &["service-manager", "list"]
+            ),
+            Ok(["--exclude", "com.apple.", "--exclude", "re:^org\\.", "--running"]
+                .map(str::to_string)
+                .to_vec())
+        );
+    }
+
+    #[test]
+    fn unknown_default_key_is_an_error() {
+        assert_eq!(
+            default_args("[list]\nshiny = true\n", &["service-manager", "list"]),
+            Err("Unknown option 'shiny' in [defaults.list]".to_string())
+        );
+    }
+
+    #[test]
+    fn finds_subcommand_after_global_option_values() {
+        let args = |argv: &[&str]| argv.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            subcommand_index(&args(&["service-manager", "list", "--running"]), "list"),
+            Some(1)
+        );
+        // The value of --config happens to be named like the subcommand.
+        assert_eq!(
+            subcommand_index(
+                &args(&["service-manager", "--config", "list", "--json", "list"]),
+                "list"
+            ),
+            Some(4)
+        );
+        assert_eq!(
+            subcommand_index(&args(&["service-manager", "--json"]), "list"),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_durations_in_every_unit() {
+        let cases = [
+            ("45s", 45),
+            ("30m", 30 * 60),
+            (" 2h ", 2 * 60 * 60),
+            ("1d", 24 * 60 * 60),
+            ("0s", 0),
+        ];
+        for (value, secs) in cases {
+            assert_eq!(
+                parse_duration(value),
+                Ok(Duration::from_secs(secs)),
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_and_overflowing_durations() {
+        for value in ["", "h", "10", "10w", "1.5h", "-1m"] {
+            assert!(parse_duration(value).is_err(), "{value:?}");
+        }
+        assert!(parse_duration(&format!("{}d", u64::MAX / 1000)).is_err());
+        assert!(parse_duration("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn parses_log_windows() {
+        assert!(matches!(
+            parse_log_window("2h"),
+            Ok(LogWindow::Last(window)) if window == Duration::from_secs(2 * 60 * 60)
+        ));
+        for timestamp in ["2024-03-01", "2024-03-01 09:30", "2024-03-01 09:30:15"] {
+            assert!(
+                matches!(parse_log_window(timestamp), Ok(LogWindow::Start(start)) if start == timestamp),
+                "{timestamp:?}"
+            );
+        }
+        assert!(parse_log_window("yesterday")
+            .unwrap_err()
+            .contains("invalid --since value 'yesterday'"));
+    }
+
+    #[test]
+    fn finds_columns_by_header_name() {
+        let columns = LaunchctlColumns::from_header("Label\tStatus\tPID").unwrap();
+        assert_eq!((columns.pid, columns.status, columns.label), (2, 1, 0));
+        assert_eq!(
+            entries("Label\tStatus\tPID\ncom.example.agent\t0\t42\n"),
+            vec![entry(Some("42"), 0, "com.example.agent")]
+        );
+    }
+
+    #[test]
+    fn accepts_names_that_launchd_allows() {
+        for name in [
+            "com.example.agent",
+            "com.example.My Sync Agent",
+            "org.example.worker[1]",
+            "com.example.it's-fine",
+            "/Library/LaunchDaemons/com.example.daemon (old).plist",
+        ] {
+            assert_eq!(parse_service_name(name).as_deref(), Ok(name));
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_control_character_names() {
+        for name in [
+            "",
+            "   ",
+            "com.example\nagent",
+            "com.example\0agent",
+            "tab\there",
+        ] {
+            assert!(parse_service_name(name).is_err(), "name {name:?}");
+        }
+    }
+
+    #[test]
+    fn treats_missing_jobs_as_already_unloaded() {
+        use std::os::unix::process::ExitStatusExt;
+        let output = |code: i32, stderr: &str| Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        assert!(bootout_not_loaded(&output(
+            3,
+            "Boot-out failed: 3: No such process\n"
+        )));
+        assert!(bootout_not_loaded(&output(113, "")));
+        assert!(bootout_not_loaded(&output(
+            0,
+            "Could not find specified service\n"
+        )));
+        assert!(!bootout_not_loaded(&output(
+            5,
+            "Boot-out failed: 5: Input/output error\n"
+        )));
+    }
+
+    #[test]
+    fn escapes_quotes_in_log_predicate() {
+        assert_eq!(
+            log_predicate(r#"say "hi"\x"#),
+            r#"subsystem == "say \"hi\"\\x" OR process == "say \"hi\"\\x" OR eventMessage CONTAINS "say \"hi\"\\x""#
+        );
+    }
+}